@@ -0,0 +1,95 @@
+use std::time::{Duration, Instant};
+
+use nrs_evm::{interpret, Context, ContextInterface, ContextState};
+use nrs_evm::core::{Hardfork, OnMemoryWorldState, TransactionContext};
+use nrs_evm::host::Host;
+use nrs_evm::instruction::{InstructionResult, JumpTable, OpcodeFn, JUMP_TABLE};
+use nrs_evm::tracer::NoopTracer;
+
+const ITERATIONS: u32 = 20;
+const GAS: u64 = 10_000_000;
+
+/// counts down from 10000, doing `(3 * 5 + 7) / 2` in each round.
+fn arithmetic_loop() -> Vec<u8> {
+    vec![
+        0x61, 0x27, 0x10, // PUSH2 10000
+        0x5b,             // JUMPDEST
+        0x60, 0x03,       // PUSH1 3
+        0x60, 0x05,       // PUSH1 5
+        0x02,             // MUL
+        0x60, 0x07,       // PUSH1 7
+        0x01,             // ADD
+        0x60, 0x02,       // PUSH1 2
+        0x90,             // SWAP1
+        0x04,             // DIV
+        0x50,             // POP
+        0x60, 0x01,       // PUSH1 1
+        0x90,             // SWAP1
+        0x03,             // SUB
+        0x80,             // DUP1
+        0x60, 0x03,       // PUSH1 3
+        0x57,             // JUMPI
+    ]
+}
+
+/// the dispatch before the jump table, for the comparison.
+/// the instruction is allocated for each step, and the context is moved in and out of it.
+struct BoxedDispatch(&'static dyn OpcodeFn);
+
+impl OpcodeFn for BoxedDispatch {
+    fn stack_io(&self) -> (usize, usize) { self.0.stack_io() }
+    fn gas_cost(&self) -> u64 { self.0.gas_cost() }
+    fn hardfork_gas_cost(&self, hardfork: Hardfork) -> u64 { self.0.hardfork_gas_cost(hardfork) }
+    fn dynamic_gas_cost(&self, ctx: &Context) -> Option<u64> { self.0.dynamic_gas_cost(ctx) }
+
+    fn state_gas_cost(&self, ctx: &Context, host: &dyn ContextInterface) -> Option<u64> {
+        self.0.state_gas_cost(ctx, host)
+    }
+
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        self.0.exec(ctx, host)
+    }
+
+    fn instruct(&self, ctx: &mut Context, host: &mut dyn ContextInterface, cost: Option<u64>) {
+        let op: Box<&dyn OpcodeFn> = Box::new(self.0);
+        let mut moved = std::mem::take(ctx);
+        op.instruct(&mut moved, host, cost);
+        *ctx = moved;
+    }
+}
+
+fn boxed_table() -> JumpTable {
+    let mut table = JumpTable::default();
+    for opcode in 0..=255 {
+        table.set(opcode, Box::leak(Box::new(BoxedDispatch(JUMP_TABLE.get(opcode)))));
+    }
+    table
+}
+
+/// runs without tracing, so that the numbers do not include the output.
+fn run(codes: &[u8], table: &JumpTable) -> Context {
+    let mut host = Host::new(OnMemoryWorldState::default(), TransactionContext::default());
+    interpret(Context::new(codes.to_vec(), GAS), table, &mut host, &mut NoopTracer)
+}
+
+fn measure(name: &str, codes: &[u8], table: &JumpTable) -> Duration {
+    let ctx = run(codes, table);
+    assert_eq!(ctx.state(), &ContextState::Success);
+    let used_gas = ctx.used_gas();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        run(codes, table);
+    }
+    let per_run = start.elapsed() / ITERATIONS;
+    eprintln!("{}: {:?} per run, {} gas, {:.1} Mgas/s",
+              name, per_run, used_gas, used_gas as f64 / per_run.as_secs_f64() / 1_000_000.0);
+    per_run
+}
+
+fn main() {
+    let codes = arithmetic_loop();
+    let jump_table = measure("jump table", &codes, &JUMP_TABLE);
+    let boxed = measure("boxed dispatch", &codes, &boxed_table());
+    eprintln!("jump table is {:.2}x faster", boxed.as_secs_f64() / jump_table.as_secs_f64());
+}
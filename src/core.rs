@@ -1,7 +1,9 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::{Cursor, Seek, SeekFrom, Write};
 
+use hash_db::Hasher;
 use hex::FromHexError;
+use keccak_hasher::KeccakHasher;
 use serde::{Deserializer, Serializer};
 use serde::de::Deserialize;
 use serde::ser::Serialize;
@@ -12,7 +14,11 @@ use crate::hex_util::{ToHex, FromHex};
 //////////   Memory Implementation    /////////
 ///////////////////////////////////////////////
 #[derive(Default, Debug)]
-pub struct Memory(Cursor<Vec<u8>>);
+pub struct Memory {
+    buf: Cursor<Vec<u8>>,
+    /// the range `(offset, length)` written last time.
+    last_write: Option<(u64, usize)>,
+}
 
 pub trait OffsetWrite<T> {
     fn write(&mut self, offset: u64, data: T) -> std::io::Result<usize>;
@@ -24,13 +30,16 @@ pub fn word_size(size: usize) -> usize {
 
 impl Memory {
     pub fn new() -> Self {
-        Memory(Cursor::new(Vec::new()))
+        Memory {
+            buf: Cursor::new(Vec::new()),
+            last_write: None,
+        }
     }
 
     pub fn allocate(&mut self, size: usize) -> std::io::Result<()> {
         if self.len() < size {
-            self.0.seek(SeekFrom::Start((size - 1) as u64))?;
-            self.0.write_all(&[0])?;
+            self.buf.seek(SeekFrom::Start((size - 1) as u64))?;
+            self.buf.write_all(&[0])?;
         }
         Ok(())
     }
@@ -44,32 +53,118 @@ impl Memory {
     pub fn read_multi_bytes(&mut self, offset: u64, length: usize) -> std::io::Result<Vec<u8>> {
         let start = offset as usize;
         let end = start + length;
-        Ok(Vec::from(&self.0.get_ref()[start..end]))
+        Ok(Vec::from(&self.buf.get_ref()[start..end]))
     }
 
     pub fn len(&self) -> usize {
-        self.0.get_ref().len()
+        self.buf.get_ref().len()
+    }
+
+    /// the total gas of the current memory, with the memory cost of the gas table.
+    pub fn gas_cost(&self, gas_table: &GasTable) -> u64 {
+        gas_table.memory_cost(self.len())
     }
 
-    pub fn gas_cost(&self) -> u64 {
-        let word_size = word_size(self.len());
-        (3 * word_size + word_size * word_size / 512) as u64
+    /// take the range `(offset, length)` written after the last call.
+    pub fn take_last_write(&mut self) -> Option<(u64, usize)> {
+        self.last_write.take()
+    }
+
+    /// additional gas to expand the memory to `size` bytes, with the memory cost of the gas table.
+    pub fn expansion_cost(&self, size: usize, gas_table: &GasTable) -> u64 {
+        if size <= self.len() {
+            0
+        } else {
+            gas_table.memory_cost(size) - self.gas_cost(gas_table)
+        }
     }
 }
 
 impl AsRef<[u8]> for Memory {
     fn as_ref(&self) -> &[u8] {
-        self.0.get_ref()
+        self.buf.get_ref()
     }
 }
 
 impl<T: AsRef<[u8]>> OffsetWrite<T> for Memory {
     fn write(&mut self, offset: u64, data: T) -> std::io::Result<usize> {
-        self.0.seek(SeekFrom::Start(offset))?;
-        self.0.write(data.as_ref())
+        self.buf.seek(SeekFrom::Start(offset))?;
+        let size = self.buf.write(data.as_ref())?;
+        self.last_write = Some((offset, size));
+        Ok(size)
+    }
+}
+
+
+///////////////////////////////////////////////
+//////////     Stack Implementation   /////////
+///////////////////////////////////////////////
+pub const STACK_LIMIT: usize = 1024;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum StackError {
+    Underflow,
+    Overflow,
+}
+
+#[derive(Default, Debug)]
+pub struct Stack(Vec<Word>);
+
+impl Stack {
+    pub fn new() -> Self {
+        Stack(Vec::new())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn push(&mut self, word: Word) -> Result<(), StackError> {
+        if self.0.len() >= STACK_LIMIT {
+            return Err(StackError::Overflow);
+        }
+        self.0.push(word);
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Result<Word, StackError> {
+        self.0.pop().ok_or(StackError::Underflow)
+    }
+
+    /// the n-th item from the top. `peek(0)` is the top item.
+    pub fn peek(&self, n: usize) -> Result<Word, StackError> {
+        match self.0.len().checked_sub(n + 1) {
+            Some(i) => Ok(self.0[i]),
+            None => Err(StackError::Underflow),
+        }
+    }
+
+    /// push a copy of the n-th item. `dup(1)` duplicates the top item like DUP1.
+    pub fn dup(&mut self, n: usize) -> Result<(), StackError> {
+        let word = self.peek(n - 1)?;
+        self.push(word)
+    }
+
+    /// swap the top item and the (n+1)-th item. `swap(1)` exchanges the top two items like SWAP1.
+    pub fn swap(&mut self, n: usize) -> Result<(), StackError> {
+        let len = self.0.len();
+        if len < n + 1 {
+            return Err(StackError::Underflow);
+        }
+        self.0.swap(len - 1, len - 1 - n);
+        Ok(())
     }
 }
 
+impl AsRef<[Word]> for Stack {
+    fn as_ref(&self) -> &[Word] {
+        &self.0
+    }
+}
 
 ///////////////////////////////////////////////
 //////////     U256 Implementation    /////////
@@ -227,6 +322,43 @@ impl From<Word> for U256 {
     }
 }
 
+///////////////////////////////////////////////
+//////////  JumpDest Implementation   /////////
+///////////////////////////////////////////////
+const OP_JUMPDEST: u8 = 0x5b;
+const OP_PUSH1: u8 = 0x60;
+const OP_PUSH32: u8 = 0x7f;
+
+/// Bitmap of the valid JUMPDEST positions in a code.
+/// JUMPDEST bytes which appear inside PUSH immediate data are not valid destinations.
+#[derive(Default, Debug)]
+pub struct JumpDestMap(Vec<u64>);
+
+impl JumpDestMap {
+    pub fn analyze(codes: &[u8]) -> Self {
+        let mut bitmap = vec![0_u64; codes.len().div_ceil(64)];
+        let mut pc = 0;
+        while pc < codes.len() {
+            let op = codes[pc];
+            if op == OP_JUMPDEST {
+                bitmap[pc / 64] |= 1 << (pc % 64);
+            } else if (OP_PUSH1..=OP_PUSH32).contains(&op) {
+                // skip immediate data
+                pc += (op - OP_PUSH1 + 1) as usize;
+            }
+            pc += 1;
+        }
+        JumpDestMap(bitmap)
+    }
+
+    pub fn is_valid(&self, pc: usize) -> bool {
+        match self.0.get(pc / 64) {
+            Some(bits) => bits & (1 << (pc % 64)) != 0,
+            None => false,
+        }
+    }
+}
+
 ///////////////////////////////////////////////
 //////////  Storage Implementation    /////////
 ///////////////////////////////////////////////
@@ -242,6 +374,35 @@ const ADDRESS_BYTE_SIZE: usize = 20;
 pub struct Address([u8; ADDRESS_BYTE_SIZE]);
 impl Address {
     pub const SIZE: usize = ADDRESS_BYTE_SIZE;
+
+    /// the address of the contract created by CREATE. keccak256(rlp([sender, nonce]))[12..]
+    pub fn create(sender: &Address, nonce: u64) -> Address {
+        let nonce_rlp = match nonce {
+            0 => vec![0x80],
+            1..=0x7f => vec![nonce as u8],
+            _ => {
+                let bytes = nonce.to_be_bytes();
+                let bytes = &bytes[bytes.iter().take_while(|b| **b == 0).count()..];
+                let mut rlp = vec![0x80 + bytes.len() as u8];
+                rlp.extend_from_slice(bytes);
+                rlp
+            },
+        };
+        let mut rlp = vec![0xc0 + (1 + ADDRESS_BYTE_SIZE + nonce_rlp.len()) as u8, 0x80 + ADDRESS_BYTE_SIZE as u8];
+        rlp.extend_from_slice(&sender.0);
+        rlp.extend_from_slice(&nonce_rlp);
+        Address::from(Word::from(&KeccakHasher::hash(&rlp)))
+    }
+
+    /// the address of the contract created by CREATE2. keccak256(0xff ++ sender ++ salt ++ keccak256(init_code))[12..]
+    pub fn create2(sender: &Address, salt: &Word, init_code: &[u8]) -> Address {
+        let mut buf = Vec::with_capacity(1 + ADDRESS_BYTE_SIZE + Word::SIZE * 2);
+        buf.push(0xff);
+        buf.extend_from_slice(&sender.0);
+        buf.extend_from_slice(salt.as_ref());
+        buf.extend_from_slice(&KeccakHasher::hash(init_code));
+        Address::from(Word::from(&KeccakHasher::hash(&buf)))
+    }
 }
 impl From<Word> for Address {
     fn from(word: Word) -> Self {
@@ -251,6 +412,12 @@ impl From<Word> for Address {
     }
 }
 
+impl From<&Address> for Word {
+    fn from(address: &Address) -> Self {
+        convert_word(&address.0, ADDRESS_BYTE_SIZE)
+    }
+}
+
 impl From<&[u8; ADDRESS_BYTE_SIZE]> for Address {
     fn from(buf: &[u8; ADDRESS_BYTE_SIZE]) -> Self {
         let mut raw: [u8; ADDRESS_BYTE_SIZE] = [0; ADDRESS_BYTE_SIZE];
@@ -290,10 +457,143 @@ pub struct Account {
     pub storage: Storage,
 }
 
+///////////////////////////////////////////////
+//////////  Hardfork Implementation  /////////
+///////////////////////////////////////////////
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Hardfork {
+    Frontier,
+    Homestead,
+    /// EIP-150
+    TangerineWhistle,
+    /// EIP-158
+    SpuriousDragon,
+    Byzantium,
+    Constantinople,
+    /// Constantinople without EIP-1283.
+    Petersburg,
+    #[default]
+    Istanbul,
+    Berlin,
+    London,
+    /// the merge.
+    Paris,
+    Shanghai,
+    Cancun,
+    Prague,
+}
+
+impl Hardfork {
+    /// the refund is capped at `used gas / max_refund_quotient` (EIP-3529).
+    pub fn max_refund_quotient(&self) -> u64 {
+        if *self >= Hardfork::London { 5 } else { 2 }
+    }
+
+    /// the precompiled contracts are at the addresses from 1 to this number.
+    pub fn precompile_count(&self) -> u8 {
+        match *self {
+            h if h >= Hardfork::Prague => 17,
+            h if h >= Hardfork::Cancun => 10,
+            h if h >= Hardfork::Istanbul => 9,
+            h if h >= Hardfork::Byzantium => 8,
+            _ => 4,
+        }
+    }
+}
+
+///////////////////////////////////////////////
+//////////  GasTable Implementation   /////////
+///////////////////////////////////////////////
+/// the gas schedule replacing the built-in costs, for the chains with their own gas prices.
+/// the fields missing in JSON are the built-in values.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(default)]
+pub struct GasTable {
+    /// the static cost by the mnemonic, like "SLOAD". the opcodes not listed cost as the hardfork defines.
+    /// the unknown mnemonics are rejected in JSON.
+    #[serde(deserialize_with = "deserialize_static_costs")]
+    pub static_costs: BTreeMap<String, u64>,
+    pub memory_word_cost: u64,
+    /// the memory cost is `memory_word_cost * words + words * words / memory_quadratic_divisor`.
+    /// zero means no quadratic cost.
+    pub memory_quadratic_divisor: u64,
+    /// the cost per word copied by CALLDATACOPY, CODECOPY, EXTCODECOPY and RETURNDATACOPY.
+    pub copy_word_cost: u64,
+    /// the cost per word hashed by SHA3 and CREATE2.
+    pub sha3_word_cost: u64,
+    /// the cost per topic of LOG1-LOG4.
+    pub log_topic_cost: u64,
+    /// the cost per byte of the data of LOG0-LOG4.
+    pub log_byte_cost: u64,
+    /// SSTORE of the non-zero value to the zero slot.
+    pub sstore_set_cost: u64,
+    /// SSTORE to the non-zero slot. after Berlin, `cold_sload_cost` is subtracted (EIP-2929).
+    pub sstore_reset_cost: u64,
+    /// the refund of SSTORE clearing the slot. `None` is the value of the hardfork.
+    pub sstore_clears_refund: Option<u64>,
+    /// the cold access cost of SLOAD and SSTORE after Berlin.
+    pub cold_sload_cost: u64,
+    /// the cold access cost of the account after Berlin.
+    pub cold_account_access_cost: u64,
+    /// CALL and CALLCODE with the value.
+    pub call_value_cost: u64,
+    /// CALL creating the account.
+    pub call_new_account_cost: u64,
+    /// SELFDESTRUCT to the account which does not exist.
+    pub selfdestruct_new_account_cost: u64,
+    /// the cost per byte of the code deployed by CREATE and CREATE2.
+    pub create_data_cost: u64,
+}
+
+/// the gas schedule of mainnet.
+pub static DEFAULT_GAS_TABLE: GasTable = GasTable {
+    static_costs: BTreeMap::new(),
+    memory_word_cost: 3,
+    memory_quadratic_divisor: 512,
+    copy_word_cost: 3,
+    sha3_word_cost: 6,
+    log_topic_cost: 375,
+    log_byte_cost: 8,
+    sstore_set_cost: 20000,
+    sstore_reset_cost: 5000,
+    sstore_clears_refund: None,
+    cold_sload_cost: 2100,
+    cold_account_access_cost: 2600,
+    call_value_cost: 9000,
+    call_new_account_cost: 25000,
+    selfdestruct_new_account_cost: 25000,
+    create_data_cost: 200,
+};
+
+impl Default for GasTable {
+    fn default() -> Self {
+        DEFAULT_GAS_TABLE.clone()
+    }
+}
+
+fn deserialize_static_costs<'de, D>(deserializer: D) -> Result<BTreeMap<String, u64>, D::Error> where
+    D: Deserializer<'de> {
+    use serde::de::Error;
+    let static_costs: BTreeMap<String, u64> = Deserialize::deserialize(deserializer)?;
+    match static_costs.keys().find(|name| crate::instruction::opcode_from_name(name).is_none()) {
+        Some(name) => Err(Error::custom(format!("unknown opcode: {}", name))),
+        None => Ok(static_costs),
+    }
+}
+
+impl GasTable {
+    /// the total gas of the memory of `size` bytes.
+    pub fn memory_cost(&self, size: usize) -> u64 {
+        let word_size = word_size(size) as u64;
+        let quadratic_cost = (word_size * word_size).checked_div(self.memory_quadratic_divisor).unwrap_or(0);
+        self.memory_word_cost * word_size + quadratic_cost
+    }
+}
+
 ///////////////////////////////////////////////
 ////////// Transaction Implementation /////////
 ///////////////////////////////////////////////
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TransactionContext {
     pub gas_price: U256,
     pub origin: Address,
@@ -301,11 +601,53 @@ pub struct TransactionContext {
     pub block_number: i64,
     pub block_timestamp: i64,
     pub block_difficulty: U256,
+    pub block_gas_limit: i64,
+    pub block_base_fee: U256,
+    /// RANDAO mix of the previous block (EIP-4399).
+    pub block_prev_randao: U256,
+    pub chain_id: U256,
+}
+
+/// an entry of the access list, in the format of JSON-RPC (EIP-2930).
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessListItem {
+    pub address: Address,
+    pub storage_keys: Vec<Word>,
+}
+
+/// the accounts and the storage slots which are warmed up before the transaction (EIP-2930).
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Clone)]
+#[serde(transparent)]
+pub struct AccessList(pub Vec<AccessListItem>);
+
+impl AccessList {
+    pub const ADDRESS_COST: u64 = 2400;
+    pub const STORAGE_KEY_COST: u64 = 1900;
+
+    /// the gas charged for the list before the execution.
+    pub fn intrinsic_gas(&self) -> u64 {
+        self.0.iter()
+            .map(|item| Self::ADDRESS_COST + Self::STORAGE_KEY_COST * item.storage_keys.len() as u64)
+            .sum()
+    }
+}
+
+///////////////////////////////////////////////
+//////////     Log Implementation     /////////
+///////////////////////////////////////////////
+/// an event emitted by LOG0-LOG4.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Clone)]
+pub struct Log {
+    pub address: Address,
+    pub topics: Vec<Word>,
+    pub data: Vec<u8>,
 }
 
 ///////////////////////////////////////////////
 ////////// CallMessage Implementation /////////
 ///////////////////////////////////////////////
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub enum CallKind {
     Call,
     DelegateCall,
@@ -316,98 +658,467 @@ pub enum CallKind {
 }
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CallMessage<'a> {
+    pub kind: CallKind,
     pub depth: u32,
     pub gas: i64,
+    /// the account whose storage and balance are used.
     pub destination: Address,
+    /// the account whose code is executed. it differs from `destination` for DELEGATECALL and CALLCODE.
+    pub code_address: Address,
     pub sender: Address,
     pub input_data: &'a [u8],
     pub value: U256,
     pub create2_salt: Word,
+    /// the state can not be modified in the frame (STATICCALL).
+    pub is_static: bool,
 }
 
 ///////////////////////////////////////////////
 //////////  WordState Implementation  /////////
 ///////////////////////////////////////////////
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
-pub struct OnMemoryWorldState(HashMap<Address, Account>);
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(transparent)]
+pub struct OnMemoryWorldState {
+    accounts: HashMap<Address, Account>,
+    /// keccak256 of the code of the accounts, computed when the code is installed.
+    #[serde(skip)]
+    code_hashes: HashMap<Address, Word>,
+    /// the storage values at the start of the transaction, recorded at the first write.
+    #[serde(skip)]
+    original_storage: HashMap<(Address, Word), Word>,
+    /// the contracts created in the transaction.
+    #[serde(skip)]
+    created: HashSet<Address>,
+    /// the accounts to be deleted at the end of the transaction.
+    #[serde(skip)]
+    destructed: HashSet<Address>,
+    /// logs emitted in the transaction, in order.
+    #[serde(skip)]
+    logs: Vec<Log>,
+    /// the accounts accessed in the transaction (EIP-2929).
+    #[serde(skip)]
+    accessed_addresses: HashSet<Address>,
+    /// the storage slots accessed in the transaction (EIP-2929).
+    #[serde(skip)]
+    accessed_storage: HashSet<(Address, Word)>,
+    /// the changes which are not committed yet, in order.
+    #[serde(skip)]
+    journal: Vec<JournalEntry>,
+}
+
+/// a position in the journal. the changes after it are undone by `revert_to`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint(usize);
+
+/// a change of the world state, with the value before the change.
+#[derive(Debug, Clone)]
+enum JournalEntry {
+    AccountCreated(Address),
+    AccountInserted { address: Address, previous: Option<Account> },
+    StorageChanged { address: Address, key: Word, previous: Word },
+    BalanceChanged { address: Address, previous: U256 },
+    NonceChanged { address: Address, previous: u64 },
+    CodeChanged { address: Address, previous: Vec<u8>, previous_hash: Word },
+    ContractCreated(Address),
+    SelfDestructed(Address),
+    /// the index of the log. the logs taken by `take_logs` are not removed again.
+    LogEmitted(usize),
+    AccountAccessed(Address),
+    StorageAccessed(Address, Word),
+}
+
+/// keccak256 of the empty code.
+pub const EMPTY_CODE_HASH: Word = Word {
+    raw: [
+        0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c, 0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7, 0x03, 0xc0,
+        0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b, 0x7b, 0xfa, 0xd8, 0x04, 0x5d, 0x85, 0xa4, 0x70,
+    ]
+};
+
+/// keccak256 of the code.
+fn code_hash(code: &[u8]) -> Word {
+    if code.is_empty() { EMPTY_CODE_HASH } else { Word::from(&KeccakHasher::hash(code)) }
+}
+
+impl<'de> Deserialize<'de> for OnMemoryWorldState {
+    fn deserialize<D>(deserializer: D) -> Result<Self, <D as Deserializer<'de>>::Error> where
+        D: Deserializer<'de> {
+        let accounts: HashMap<Address, Account> = Deserialize::deserialize(deserializer)?;
+        let code_hashes = accounts.iter()
+            .map(|(address, a)| (address.clone(), code_hash(&a.code)))
+            .collect();
+        Ok(OnMemoryWorldState { accounts, code_hashes, .. OnMemoryWorldState::default() })
+    }
+}
 
+/// the status of SSTORE, decided by the original, current and new value (EIP-2200).
 #[derive(Debug, PartialEq, Eq)]
 pub enum StorageStatus {
+    /// the new value is the same as the current value.
     StorageUnchanged,
+    /// X -> Y, where X is not zero.
     StorageModified,
+    /// X -> Y -> Z, the slot is already dirty and no refund is changed.
     StorageModifiedAgain,
+    /// 0 -> Y
     StorageAdded,
+    /// X -> 0
     StorageDeleted,
+    /// X -> 0 -> Z
+    StorageDeletedAdded,
+    /// X -> Y -> 0
+    StorageModifiedDeleted,
+    /// X -> 0 -> X
+    StorageDeletedRestored,
+    /// 0 -> Y -> 0
+    StorageAddedDeleted,
+    /// X -> Y -> X
+    StorageModifiedRestored,
+}
+
+impl StorageStatus {
+    pub fn new(original: &Word, current: &Word, value: &Word) -> Self {
+        if current == value {
+            return StorageStatus::StorageUnchanged;
+        }
+        if original == current {
+            return if *original == Word::ZERO {
+                StorageStatus::StorageAdded
+            } else if *value == Word::ZERO {
+                StorageStatus::StorageDeleted
+            } else {
+                StorageStatus::StorageModified
+            };
+        }
+        if *original == Word::ZERO {
+            return if *value == Word::ZERO {
+                StorageStatus::StorageAddedDeleted
+            } else {
+                StorageStatus::StorageModifiedAgain
+            };
+        }
+        if *current == Word::ZERO {
+            if original == value {
+                StorageStatus::StorageDeletedRestored
+            } else {
+                StorageStatus::StorageDeletedAdded
+            }
+        } else if *value == Word::ZERO {
+            StorageStatus::StorageModifiedDeleted
+        } else if original == value {
+            StorageStatus::StorageModifiedRestored
+        } else {
+            StorageStatus::StorageModifiedAgain
+        }
+    }
+}
+
+/// whether the account or the storage slot is already accessed in the transaction (EIP-2929).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessStatus {
+    Cold,
+    Warm,
 }
 
 pub trait WorldStateInterface {
     fn account_exists(&self, address: &Address) -> bool;
     fn set_storage(&mut self, address: &Address, key: &Word, value: Word) -> StorageStatus;
     fn get_storage(&self, address: &Address, key: &Word) -> Word;
+    /// the storage value at the start of the transaction.
+    fn get_original_storage(&self, address: &Address, key: &Word) -> Word;
     fn get_balance(&self, address: &Address) -> U256;
     fn get_code_size(&self, address: &Address) -> usize;
     fn get_code_hash(&self, address: &Address) -> Word;
     fn copy_code(&self, address: &Address, buf: &mut [u8]) -> usize;
+    /// move the balance to `beneficiary` and register the account to be deleted at the end of the transaction.
+    /// `false` if the account is already registered.
     fn selfdestruct(&mut self, address: &Address, beneficiary: &Address) -> bool;
+    /// create the account of a new contract. the balance is kept if it exists.
+    fn create_account(&mut self, address: &Address);
+    /// the contract is created in the current transaction (EIP-6780).
+    fn is_created(&self, address: &Address) -> bool;
+    fn get_nonce(&self, address: &Address) -> u64;
+    /// the account is created if it does not exist.
+    fn increment_nonce(&mut self, address: &Address);
+    /// install the code. the account is created if it does not exist.
+    fn set_code(&mut self, address: &Address, code: Vec<u8>);
+    /// move `value` from `from` to `to`. `to` is created if it does not exist.
+    /// `false` if the balance of `from` is not enough.
+    fn transfer(&mut self, from: &Address, to: &Address, value: U256) -> bool;
+    fn emit_log(&mut self, log: Log);
+    /// logs emitted in the transaction, in order.
+    fn logs(&self) -> &[Log];
+    /// the taken logs are not removed by `revert_to`.
+    fn take_logs(&mut self) -> Vec<Log>;
+    /// the changes after the checkpoint, including the logs, can be undone by `revert_to`.
+    fn checkpoint(&mut self) -> Checkpoint;
+    fn revert_to(&mut self, checkpoint: Checkpoint);
+    /// fix all the changes. they can not be reverted after this.
+    fn commit(&mut self);
+    /// mark the account as accessed in the transaction, and return the status before it.
+    fn access_account(&mut self, address: &Address) -> AccessStatus;
+    /// mark the storage slot as accessed in the transaction, and return the status before it.
+    fn access_storage(&mut self, address: &Address, key: &Word) -> AccessStatus;
+    /// the status of the account without marking it as accessed.
+    fn account_access_status(&self, address: &Address) -> AccessStatus;
+    /// the status of the storage slot without marking it as accessed.
+    fn storage_access_status(&self, address: &Address, key: &Word) -> AccessStatus;
 }
 
 impl OnMemoryWorldState {
+    /// set the account directly. the previous account is restored by `revert_to`.
     pub fn insert(&mut self, address: Address, account: Account) -> Option<Account>{
-        self.0.insert(address, account)
+        self.code_hashes.insert(address.clone(), code_hash(&account.code));
+        let previous = self.accounts.insert(address.clone(), account);
+        self.journal.push(JournalEntry::AccountInserted { address, previous: previous.clone() });
+        previous
+    }
+
+    /// the account is created if it does not exist.
+    fn account_mut(&mut self, address: &Address) -> &mut Account {
+        if !self.accounts.contains_key(address) {
+            self.journal.push(JournalEntry::AccountCreated(address.clone()));
+        }
+        self.accounts.entry(address.clone()).or_insert_with(|| Account {
+            address: address.clone(),
+            .. Account::default()
+        })
+    }
+
+    fn undo(&mut self, entry: JournalEntry) {
+        match entry {
+            JournalEntry::AccountCreated(address) => {
+                self.accounts.remove(&address);
+                self.code_hashes.remove(&address);
+            },
+            JournalEntry::AccountInserted { address, previous: Some(previous) } => {
+                self.code_hashes.insert(address.clone(), code_hash(&previous.code));
+                self.accounts.insert(address, previous);
+            },
+            JournalEntry::AccountInserted { address, previous: None } => {
+                self.accounts.remove(&address);
+                self.code_hashes.remove(&address);
+            },
+            JournalEntry::StorageChanged { address, key, previous } => {
+                if let Some(a) = self.accounts.get_mut(&address) {
+                    a.storage.0.insert(key, previous);
+                }
+            },
+            JournalEntry::BalanceChanged { address, previous } => {
+                if let Some(a) = self.accounts.get_mut(&address) {
+                    a.balance = previous;
+                }
+            },
+            JournalEntry::NonceChanged { address, previous } => {
+                if let Some(a) = self.accounts.get_mut(&address) {
+                    a.nonce = previous;
+                }
+            },
+            JournalEntry::CodeChanged { address, previous, previous_hash } => {
+                if let Some(a) = self.accounts.get_mut(&address) {
+                    a.code = previous;
+                    self.code_hashes.insert(address, previous_hash);
+                }
+            },
+            JournalEntry::ContractCreated(address) => {
+                self.created.remove(&address);
+            },
+            JournalEntry::SelfDestructed(address) => {
+                self.destructed.remove(&address);
+            },
+            JournalEntry::LogEmitted(index) => {
+                self.logs.truncate(index);
+            },
+            JournalEntry::AccountAccessed(address) => {
+                self.accessed_addresses.remove(&address);
+            },
+            JournalEntry::StorageAccessed(address, key) => {
+                self.accessed_storage.remove(&(address, key));
+            },
+        }
+    }
+
+    fn set_balance(&mut self, address: &Address, balance: U256) {
+        let account = self.account_mut(address);
+        let previous = std::mem::replace(&mut account.balance, balance);
+        self.journal.push(JournalEntry::BalanceChanged { address: address.clone(), previous });
+    }
+
+    fn set_nonce(&mut self, address: &Address, nonce: u64) {
+        let account = self.account_mut(address);
+        let previous = std::mem::replace(&mut account.nonce, nonce);
+        self.journal.push(JournalEntry::NonceChanged { address: address.clone(), previous });
+    }
+
+    /// finish the transaction. the current storage values become the original values of the next one,
+    /// and the self-destructed accounts are deleted. the changes are committed.
+    pub fn finalize_transaction(&mut self) {
+        self.commit();
+        self.original_storage.clear();
+        self.created.clear();
+        self.accessed_addresses.clear();
+        self.accessed_storage.clear();
+        for address in self.destructed.drain() {
+            self.accounts.remove(&address);
+            self.code_hashes.remove(&address);
+        }
     }
 }
 impl WorldStateInterface for OnMemoryWorldState {
     fn account_exists(&self, address: &Address) -> bool {
-        self.0.get(address).is_some()
+        self.accounts.get(address).is_some()
     }
 
     fn set_storage(&mut self, address: &Address, key: &Word, value: Word) -> StorageStatus {
-        let res = match self.0.get(address) {
+        let current = self.get_storage(address, key);
+        let original = *self.original_storage.entry((address.clone(), *key)).or_insert(current);
+        self.account_mut(address).storage.0.insert(*key, value);
+        self.journal.push(JournalEntry::StorageChanged { address: address.clone(), key: *key, previous: current });
+        StorageStatus::new(&original, &current, &value)
+    }
+
+    fn get_storage(&self, address: &Address, key: &Word) -> Word {
+        self.accounts.get(address)
+            .and_then(|a| a.storage.0.get(key))
+            .map_or(Word::ZERO, |value| *value)
+    }
+
+    fn get_original_storage(&self, address: &Address, key: &Word) -> Word {
+        match self.original_storage.get(&(address.clone(), *key)) {
+            Some(value) => *value,
+            None => self.get_storage(address, key),
+        }
+    }
+
+    fn get_balance(&self, address: &Address) -> U256 {
+        self.accounts.get(address).map_or(U256::zero(), |a| a.balance)
+    }
+
+    fn get_code_size(&self, address: &Address) -> usize {
+        self.accounts.get(address).map_or(0, |a| a.code.len())
+    }
+
+    /// keccak256 of the code. zero for the account which does not exist.
+    fn get_code_hash(&self, address: &Address) -> Word {
+        match self.accounts.get(address) {
+            Some(a) if a.code.is_empty() => EMPTY_CODE_HASH,
+            Some(_) => self.code_hashes[address],
+            None => Word::ZERO,
+        }
+    }
+
+    /// copy the code from the beginning to `buf` and return the copied size.
+    fn copy_code(&self, address: &Address, buf: &mut [u8]) -> usize {
+        match self.accounts.get(address) {
             Some(a) => {
-                match a.storage.0.get(key) {
-                    Some(&Word::ZERO) | None => StorageStatus::StorageAdded,
-                    Some(w) => {
-                        if value == Word::ZERO {
-                            StorageStatus::StorageDeleted
-                        } else if &value == w {
-                            StorageStatus::StorageUnchanged
-                        } else {
-                            StorageStatus::StorageModified
-                        }
-                    },
-                }
+                let size = std::cmp::min(buf.len(), a.code.len());
+                buf[..size].copy_from_slice(&a.code[..size]);
+                size
             },
-            None => panic!("set_storage: Account: {:?} is not exists!", &address),
+            None => 0,
+        }
+    }
+
+    fn selfdestruct(&mut self, address: &Address, beneficiary: &Address) -> bool {
+        let balance = self.get_balance(address);
+        self.transfer(address, beneficiary, balance);
+        if !self.destructed.insert(address.clone()) {
+            return false;
+        }
+        self.journal.push(JournalEntry::SelfDestructed(address.clone()));
+        true
+    }
+
+    fn create_account(&mut self, address: &Address) {
+        self.account_mut(address);
+        if self.created.insert(address.clone()) {
+            self.journal.push(JournalEntry::ContractCreated(address.clone()));
+        }
+    }
+
+    fn is_created(&self, address: &Address) -> bool {
+        self.created.contains(address)
+    }
+
+    fn get_nonce(&self, address: &Address) -> u64 {
+        self.accounts.get(address).map_or(0, |a| a.nonce)
+    }
+
+    fn increment_nonce(&mut self, address: &Address) {
+        let nonce = self.get_nonce(address);
+        self.set_nonce(address, nonce + 1);
+    }
+
+    fn set_code(&mut self, address: &Address, code: Vec<u8>) {
+        let hash = code_hash(&code);
+        let previous = std::mem::replace(&mut self.account_mut(address).code, code);
+        let previous_hash = self.code_hashes.insert(address.clone(), hash).unwrap_or(EMPTY_CODE_HASH);
+        self.journal.push(JournalEntry::CodeChanged { address: address.clone(), previous, previous_hash });
+    }
+
+    fn transfer(&mut self, from: &Address, to: &Address, value: U256) -> bool {
+        if value.is_zero() {
+            return true;
+        }
+        let from_balance = match self.accounts.get(from) {
+            Some(a) if a.balance >= value => a.balance,
+            _ => return false,
         };
-        let account = self.0.get_mut(address).unwrap();
-        account.storage.0.insert(key.clone(), value);
-        res
+        self.set_balance(from, from_balance - value);
+        let to_balance = self.get_balance(to);
+        self.set_balance(to, to_balance + value);
+        true
     }
 
-    fn get_storage(&self, address: &Address, key: &Word) -> Word {
-        match self.0.get(address) {
-            Some(a) => a.storage.0.get(key).unwrap_or(&Word::ZERO).clone(),
-            None => panic!("get_storage: Account: {:?} is not exists!", &address)
+    fn emit_log(&mut self, log: Log) {
+        self.journal.push(JournalEntry::LogEmitted(self.logs.len()));
+        self.logs.push(log);
+    }
+
+    fn logs(&self) -> &[Log] {
+        &self.logs
+    }
+
+    fn take_logs(&mut self) -> Vec<Log> {
+        std::mem::take(&mut self.logs)
+    }
+
+    fn checkpoint(&mut self) -> Checkpoint {
+        Checkpoint(self.journal.len())
+    }
+
+    fn revert_to(&mut self, checkpoint: Checkpoint) {
+        while self.journal.len() > checkpoint.0 {
+            let entry = self.journal.pop().unwrap();
+            self.undo(entry);
         }
     }
 
-    fn get_balance(&self, _address: &Address) -> U256 {
-        unimplemented!()
+    fn commit(&mut self) {
+        self.journal.clear();
     }
 
-    fn get_code_size(&self, _address: &Address) -> usize {
-        unimplemented!()
+    fn access_account(&mut self, address: &Address) -> AccessStatus {
+        if !self.accessed_addresses.insert(address.clone()) {
+            return AccessStatus::Warm;
+        }
+        self.journal.push(JournalEntry::AccountAccessed(address.clone()));
+        AccessStatus::Cold
     }
 
-    fn get_code_hash(&self, _address: &Address) -> Word {
-        unimplemented!()
+    fn access_storage(&mut self, address: &Address, key: &Word) -> AccessStatus {
+        if !self.accessed_storage.insert((address.clone(), *key)) {
+            return AccessStatus::Warm;
+        }
+        self.journal.push(JournalEntry::StorageAccessed(address.clone(), *key));
+        AccessStatus::Cold
     }
 
-    fn copy_code(&self, _address: &Address, _buf: &mut [u8]) -> usize {
-        unimplemented!()
+    fn account_access_status(&self, address: &Address) -> AccessStatus {
+        if self.accessed_addresses.contains(address) { AccessStatus::Warm } else { AccessStatus::Cold }
     }
 
-    fn selfdestruct(&mut self, _address: &Address, _beneficiary: &Address) -> bool {
-        unimplemented!()
+    fn storage_access_status(&self, address: &Address, key: &Word) -> AccessStatus {
+        if self.accessed_storage.contains(&(address.clone(), *key)) { AccessStatus::Warm } else { AccessStatus::Cold }
     }
 }
@@ -4,6 +4,12 @@ pub trait ToHex {
     fn to_hex(&self) -> String;
 }
 
+impl ToHex for [u8] {
+    fn to_hex(&self) -> String {
+        hex::encode(self)
+    }
+}
+
 pub trait FromHex<T> {
     fn from_hex(hex_str: &str) -> Result<T, FromHexError>;
 }
@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use crate::{cap_refund, execute_call, CallResult, ContextInterface, ContextState};
+use crate::core::*;
+use crate::instruction::{JumpTable, JUMP_TABLE};
+use crate::tracer::{NoopTracer, Tracer};
+
+/// `ContextInterface` backed by a world state and the context of the transaction.
+pub struct Host<W: WorldStateInterface> {
+    world_state: W,
+    tx_context: TransactionContext,
+    block_hashes: HashMap<u64, Word>,
+    hardfork: Hardfork,
+    access_list: AccessList,
+    /// `None` is `JUMP_TABLE`. it is shared with the frames without copying.
+    table: Option<Arc<JumpTable>>,
+}
+
+impl<W: WorldStateInterface> Host<W> {
+    pub fn new(world_state: W, tx_context: TransactionContext) -> Self {
+        Host {
+            world_state,
+            tx_context,
+            block_hashes: HashMap::new(),
+            hardfork: Hardfork::default(),
+            access_list: AccessList::default(),
+            table: None,
+        }
+    }
+
+    /// the hardfork for the messages given to `call`.
+    pub fn with_hardfork(mut self, hardfork: Hardfork) -> Self {
+        self.hardfork = hardfork;
+        self
+    }
+
+    /// the jump table for the messages given to `call`, like the one with the custom gas table.
+    pub fn with_jump_table(mut self, table: JumpTable) -> Self {
+        self.table = Some(Arc::new(table));
+        self
+    }
+
+    /// the access list of the transaction. it is charged and warmed up when the transaction starts from Berlin.
+    pub fn with_access_list(mut self, access_list: AccessList) -> Self {
+        self.access_list = access_list;
+        self
+    }
+
+    /// register the hash of a past block for BLOCKHASH.
+    pub fn set_block_hash(&mut self, number: u64, hash: Word) {
+        self.block_hashes.insert(number, hash);
+    }
+
+    pub fn world_state(&self) -> &W {
+        &self.world_state
+    }
+
+    pub fn world_state_mut(&mut self) -> &mut W {
+        &mut self.world_state
+    }
+
+    /// take the logs emitted in the execution, in order.
+    pub fn take_logs(&mut self) -> Vec<Log> {
+        self.world_state.take_logs()
+    }
+
+    pub fn into_world_state(self) -> W {
+        self.world_state
+    }
+
+    /// execute the code of the account with the tracer.
+    /// the message of depth 0 starts a transaction. the gas for the access list is charged,
+    /// the accounts accessed by the transaction are warmed up, and the refund is capped by the gas used
+    /// in the whole transaction (EIP-3529).
+    pub fn call_with_tracer<T: Tracer + ?Sized>(&mut self, message: &CallMessage, tracer: &mut T) -> CallResult {
+        let hardfork = self.hardfork;
+        let table = self.table.clone();
+        let table = table.as_deref().unwrap_or(&JUMP_TABLE);
+        if message.depth > 0 {
+            return execute_call(self, message, hardfork, table, tracer);
+        }
+        // the access list and the warm addresses are introduced in Berlin (EIP-2929, EIP-2930).
+        let intrinsic_gas = if hardfork >= Hardfork::Berlin { self.access_list.intrinsic_gas() } else { 0 };
+        let gas = match i64::try_from(intrinsic_gas).ok().and_then(|cost| message.gas.checked_sub(cost)) {
+            Some(gas) if gas >= 0 => gas,
+            _ => return CallResult {
+                state: ContextState::OutOfGas,
+                output: Vec::new(),
+                gas_left: 0,
+                refund_gas: 0,
+                create_address: Address::default(),
+                logs: Vec::new(),
+            },
+        };
+        if hardfork >= Hardfork::Berlin {
+            self.warm_up(message);
+        }
+        let mut result = execute_call(self, &CallMessage { gas, .. message.clone() }, hardfork, table, tracer);
+        let used_gas = message.gas as u64 - result.gas_left;
+        result.refund_gas = cap_refund(result.refund_gas, used_gas, hardfork);
+        result
+    }
+
+    /// mark the sender, the recipient, the coinbase (EIP-3651), the precompiled contracts
+    /// and the access list (EIP-2930) as accessed (EIP-2929).
+    fn warm_up(&mut self, message: &CallMessage) {
+        self.world_state.access_account(&message.sender);
+        if let CallKind::Call | CallKind::CallCode | CallKind::DelegateCall | CallKind::StaticCall = message.kind {
+            self.world_state.access_account(&message.destination);
+        }
+        if self.hardfork >= Hardfork::Shanghai {
+            self.world_state.access_account(&self.tx_context.block_coinbase);
+        }
+        for i in 1..=self.hardfork.precompile_count() {
+            self.world_state.access_account(&Address::from(Word::from(U256::from(i))));
+        }
+        for item in &self.access_list.0 {
+            self.world_state.access_account(&item.address);
+            for key in &item.storage_keys {
+                self.world_state.access_storage(&item.address, key);
+            }
+        }
+    }
+}
+
+impl<W: WorldStateInterface> ContextInterface for Host<W> {
+    fn account_exists(&self, address: &Address) -> bool {
+        self.world_state.account_exists(address)
+    }
+
+    fn set_storage(&mut self, address: &Address, key: &Word, value: &Word) -> StorageStatus {
+        self.world_state.set_storage(address, key, *value)
+    }
+
+    fn get_storage(&self, address: &Address, key: &Word) -> Word {
+        self.world_state.get_storage(address, key)
+    }
+
+    fn get_original_storage(&self, address: &Address, key: &Word) -> Word {
+        self.world_state.get_original_storage(address, key)
+    }
+
+    fn get_balance(&self, address: &Address) -> U256 {
+        self.world_state.get_balance(address)
+    }
+
+    fn get_code_size(&self, address: &Address) -> usize {
+        self.world_state.get_code_size(address)
+    }
+
+    fn get_code_hash(&self, address: &Address) -> Word {
+        self.world_state.get_code_hash(address)
+    }
+
+    fn copy_code(&self, address: &Address, buf: &mut [u8]) -> usize {
+        self.world_state.copy_code(address, buf)
+    }
+
+    fn selfdestruct(&mut self, address: &Address, beneficiary: &Address) -> bool {
+        self.world_state.selfdestruct(address, beneficiary)
+    }
+
+    fn create_account(&mut self, address: &Address) {
+        self.world_state.create_account(address)
+    }
+
+    fn is_created(&self, address: &Address) -> bool {
+        self.world_state.is_created(address)
+    }
+
+    fn transfer(&mut self, from: &Address, to: &Address, value: U256) -> bool {
+        self.world_state.transfer(from, to, value)
+    }
+
+    fn get_nonce(&self, address: &Address) -> u64 {
+        self.world_state.get_nonce(address)
+    }
+
+    fn increment_nonce(&mut self, address: &Address) {
+        self.world_state.increment_nonce(address)
+    }
+
+    fn set_code(&mut self, address: &Address, code: Vec<u8>) {
+        self.world_state.set_code(address, code)
+    }
+
+    /// execute the code of the account without tracing. see `call_with_tracer`.
+    fn call(&mut self, message: &CallMessage) -> CallResult {
+        self.call_with_tracer(message, &mut NoopTracer)
+    }
+
+    fn get_tx_context(&self) -> TransactionContext {
+        self.tx_context.clone()
+    }
+
+    /// zero for the block which is not registered.
+    fn get_block_hash(&self, number: u64) -> Word {
+        self.block_hashes.get(&number).cloned().unwrap_or(Word::ZERO)
+    }
+
+    fn emit_log(&mut self, address: &Address, data: &[u8], topics: &[Word]) {
+        self.world_state.emit_log(Log {
+            address: address.clone(),
+            topics: topics.to_vec(),
+            data: data.to_vec(),
+        });
+    }
+
+    fn logs(&self) -> &[Log] {
+        self.world_state.logs()
+    }
+
+    fn checkpoint(&mut self) -> Checkpoint {
+        self.world_state.checkpoint()
+    }
+
+    fn revert_to(&mut self, checkpoint: Checkpoint) {
+        self.world_state.revert_to(checkpoint)
+    }
+
+    fn access_account(&mut self, address: &Address) -> AccessStatus {
+        self.world_state.access_account(address)
+    }
+
+    fn access_storage(&mut self, address: &Address, key: &Word) -> AccessStatus {
+        self.world_state.access_storage(address, key)
+    }
+
+    fn account_access_status(&self, address: &Address) -> AccessStatus {
+        self.world_state.account_access_status(address)
+    }
+
+    fn storage_access_status(&self, address: &Address, key: &Word) -> AccessStatus {
+        self.world_state.storage_access_status(address, key)
+    }
+}
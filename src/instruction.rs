@@ -1,802 +1,2544 @@
-use crate::{Context, ContextState};
+use std::sync::Arc;
+
+use crate::{Context, ContextInterface, ContextState};
 use crate::core::*;
 use keccak_hasher::KeccakHasher;
 use hash_db::Hasher;
 
-type InstructionResult = Result<Context, Context>;
+/// `Err` is the state of an exceptional halt.
+pub type InstructionResult = Result<(), ContextState>;
 
-fn out_of_gas(mut ctx: Context) -> Context {
-    ctx.state = ContextState::OutOfGas;
+fn halt(ctx: &mut Context, state: ContextState) {
+    ctx.state = state;
     ctx.pc = ctx.codes.len();
-    ctx
 }
 
-fn invalid(mut ctx: Context) -> Context {
-    ctx.state = ContextState::Invalid;
-    ctx.pc = ctx.codes.len();
-    ctx
+/// exceptional halt. all of the given gas is consumed.
+fn exceptional_halt(ctx: &mut Context, state: ContextState) {
+    ctx.used_gas = ctx.remaining_gas;
+    ctx.return_data.clear();
+    halt(ctx, state)
+}
+
+/// peek the n-th item from the top of the stack without popping it.
+/// the stack depth is already validated with `stack_io`.
+fn stack_peek(ctx: &Context, n: usize) -> U256 {
+    ctx.stack.peek(n).map(U256::from).unwrap_or_else(|_| U256::from(0))
+}
+
+/// validate the stack depth and charge `cost` given by `instruction_gas_cost` before the execution.
+fn charge<T: OpcodeFn + ?Sized>(op: &T, ctx: &mut Context, cost: Option<u64>) -> InstructionResult {
+    let (inputs, outputs) = op.stack_io();
+    if ctx.stack.len() < inputs {
+        return Err(ContextState::StackUnderflow);
+    }
+    if ctx.stack.len() - inputs + outputs > STACK_LIMIT {
+        return Err(ContextState::StackOverflow);
+    }
+    let cost = match cost {
+        Some(cost) => cost,
+        None => return Err(ContextState::OutOfGas),
+    };
+    if cost > ctx.gas_left() {
+        return Err(ContextState::OutOfGas);
+    }
+    ctx.used_gas += cost;
+    Ok(())
+}
+
+/// the gas charged before the execution of the instruction at `ctx.pc`.
+/// the static cost in the gas table of `table` takes precedence over the one of the instruction.
+/// `None` means the cost can never be paid.
+pub fn instruction_gas_cost<T: OpcodeFn + ?Sized>(op: &T, table: &JumpTable, ctx: &Context, host: &dyn ContextInterface) -> Option<u64> {
+    let static_cost = match table.static_costs[ctx.codes[ctx.pc] as usize] {
+        Some(cost) => cost,
+        None => op.hardfork_gas_cost(ctx.hardfork),
+    };
+    Some(static_cost.saturating_add(op.dynamic_gas_cost(ctx)?).saturating_add(op.state_gas_cost(ctx, host)?))
+}
+
+/// charge the gas which is decided in the execution.
+fn use_gas(ctx: &mut Context, cost: u64) -> InstructionResult {
+    if cost > ctx.gas_left() {
+        return Err(ContextState::OutOfGas);
+    }
+    ctx.used_gas += cost;
+    Ok(())
+}
+
+const WARM_STORAGE_READ_COST: u64 = 100;
+/// the cost to access the other account from Tangerine Whistle to Berlin (EIP-150).
+const ACCOUNT_ACCESS_GAS: u64 = 700;
+
+/// the cost to access the other account, charged before the execution.
+/// after Berlin, it is the warm cost and the cold access is added by `cold_account_cost`.
+fn account_access_cost(hardfork: Hardfork, frontier_cost: u64) -> u64 {
+    match hardfork {
+        h if h >= Hardfork::Berlin => WARM_STORAGE_READ_COST,
+        h if h >= Hardfork::TangerineWhistle => ACCOUNT_ACCESS_GAS,
+        _ => frontier_cost,
+    }
+}
+
+/// BALANCE and EXTCODEHASH cost 400 until they are repriced in Istanbul (EIP-1884).
+fn balance_cost(hardfork: Hardfork) -> u64 {
+    if hardfork >= Hardfork::TangerineWhistle && hardfork < Hardfork::Istanbul {
+        400
+    } else {
+        account_access_cost(hardfork, 20)
+    }
+}
+
+/// the address at the stack position `n` before the execution.
+fn peek_address(ctx: &Context, n: usize) -> Address {
+    Address::from(Word::from(stack_peek(ctx, n)))
+}
+
+/// the cold access cost in addition to the warm one after Berlin (EIP-2929).
+/// the account is marked as accessed in the execution.
+fn cold_account_cost(ctx: &Context, host: &dyn ContextInterface, address: &Address) -> u64 {
+    if ctx.hardfork >= Hardfork::Berlin && host.account_access_status(address) == AccessStatus::Cold {
+        ctx.gas_table().cold_account_access_cost.saturating_sub(WARM_STORAGE_READ_COST)
+    } else {
+        0
+    }
 }
 
-pub trait OpcodeFn {
+pub trait OpcodeFn: Sync {
+    /// the number of the stack items (inputs, outputs).
+    fn stack_io(&self) -> (usize, usize);
+    /// the static gas on the default hardfork.
     fn gas_cost(&self) -> u64;
-    fn exec(&self, ctx: Context) -> Context;
-    fn instruct(&self, ctx: Context) -> Context {
-        let mut res = self.exec(ctx);
-        res.used_gas += self.gas_cost();
-        res
+    /// the static gas on the hardfork. it differs from `gas_cost` only for the repriced instructions.
+    fn hardfork_gas_cost(&self, _hardfork: Hardfork) -> u64 { self.gas_cost() }
+    /// additional gas depending on the operands, like memory expansion.
+    /// `None` means the cost can never be paid.
+    fn dynamic_gas_cost(&self, _ctx: &Context) -> Option<u64> { Some(0) }
+    /// additional gas depending on the world state, like SSTORE. it is decided without changing the state.
+    /// `None` means the cost can never be paid.
+    fn state_gas_cost(&self, _ctx: &Context, _host: &dyn ContextInterface) -> Option<u64> { Some(0) }
+    /// execute the instruction. the world state is accessed through `host`.
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult;
+    /// charge `cost` given by `instruction_gas_cost` and execute.
+    /// if the gas is not enough, the instruction is not executed.
+    fn instruct(&self, ctx: &mut Context, host: &mut dyn ContextInterface, cost: Option<u64>) {
+        if let Err(state) = charge(self, ctx, cost).and_then(|_| self.exec(ctx, host)) {
+            exceptional_halt(ctx, state);
+        }
     }
 }
 
 pub struct OpAdd;
 
 impl OpcodeFn for OpAdd {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 3 }
 
-    fn exec(&self, mut ctx: Context) -> Context {
-        let result = U256::from(ctx.stack.pop().unwrap()).overflowing_add(U256::from(ctx.stack.pop().unwrap()));
-        ctx.stack.push(Word::from(result.0));
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let result = U256::from(ctx.stack.pop()?).overflowing_add(U256::from(ctx.stack.pop()?));
+        ctx.stack.push(Word::from(result.0))?;
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpMul;
 
 impl OpcodeFn for OpMul {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 5 }
 
-    fn exec(&self, mut ctx: Context) -> Context {
-        let result = U256::from(ctx.stack.pop().unwrap()).overflowing_mul(U256::from(ctx.stack.pop().unwrap()));
-        ctx.stack.push(Word::from(result.0));
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let result = U256::from(ctx.stack.pop()?).overflowing_mul(U256::from(ctx.stack.pop()?));
+        ctx.stack.push(Word::from(result.0))?;
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpSub;
 
 impl OpcodeFn for OpSub {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 3 }
 
-    fn exec(&self, mut ctx: Context) -> Context {
-        let result = U256::from(ctx.stack.pop().unwrap()).overflowing_sub(U256::from(ctx.stack.pop().unwrap()));
-        ctx.stack.push(Word::from(result.0));
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let result = U256::from(ctx.stack.pop()?).overflowing_sub(U256::from(ctx.stack.pop()?));
+        ctx.stack.push(Word::from(result.0))?;
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpDiv;
 
 impl OpcodeFn for OpDiv {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 5 }
 
-    fn exec(&self, mut ctx: Context) -> Context {
-        let a = U256::from(ctx.stack.pop().unwrap());
-        let b = U256::from(ctx.stack.pop().unwrap());
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let a = U256::from(ctx.stack.pop()?);
+        let b = U256::from(ctx.stack.pop()?);
         if b.is_zero() {
-            ctx.stack.push(Word::from(U256::from(0)))
+            ctx.stack.push(Word::from(U256::from(0)))?
         } else {
             let result = a / b;
-            ctx.stack.push(Word::from(result));
+            ctx.stack.push(Word::from(result))?;
         }
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpSDiv;
 
 impl OpcodeFn for OpSDiv {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 5 }
 
-    fn exec(&self, mut ctx: Context) -> Context {
-        let a = U256::from(ctx.stack.pop().unwrap());
-        let b = U256::from(ctx.stack.pop().unwrap());
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let a = U256::from(ctx.stack.pop()?);
+        let b = U256::from(ctx.stack.pop()?);
         if b.is_zero() {
-            ctx.stack.push(Word::from(U256::from(0)))
+            ctx.stack.push(Word::from(U256::from(0)))?
         } else {
             if a.is_negative() ^ b.is_negative() {
-                ctx.stack.push(Word::from((a.abs() / b.abs()).to_negative()));
+                ctx.stack.push(Word::from((a.abs() / b.abs()).to_negative()))?;
             } else {
-                ctx.stack.push(Word::from(a / b));
+                ctx.stack.push(Word::from(a / b))?;
             }
         }
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpMod;
 
 impl OpcodeFn for OpMod {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 5 }
 
-    fn exec(&self, mut ctx: Context) -> Context {
-        let a = U256::from(ctx.stack.pop().unwrap());
-        let b = U256::from(ctx.stack.pop().unwrap());
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let a = U256::from(ctx.stack.pop()?);
+        let b = U256::from(ctx.stack.pop()?);
         if b.is_zero() {
-            ctx.stack.push(Word::from(U256::from(0)))
+            ctx.stack.push(Word::from(U256::from(0)))?
         } else {
-            ctx.stack.push(Word::from(a % b));
+            ctx.stack.push(Word::from(a % b))?;
         }
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpSMod;
 
 impl OpcodeFn for OpSMod {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 5 }
 
-    fn exec(&self, mut ctx: Context) -> Context {
-        let a = U256::from(ctx.stack.pop().unwrap());
-        let b = U256::from(ctx.stack.pop().unwrap());
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let a = U256::from(ctx.stack.pop()?);
+        let b = U256::from(ctx.stack.pop()?);
         if b.is_zero() {
-            ctx.stack.push(Word::from(U256::from(0)))
+            ctx.stack.push(Word::from(U256::from(0)))?
         } else {
             if a.is_negative() {
-                ctx.stack.push(Word::from((a.abs() % b).to_negative()));
+                ctx.stack.push(Word::from((a.abs() % b).to_negative()))?;
             } else {
-                ctx.stack.push(Word::from(a % b));
+                ctx.stack.push(Word::from(a % b))?;
             }
         }
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpAddMod;
 
 impl OpcodeFn for OpAddMod {
+    fn stack_io(&self) -> (usize, usize) { (3, 1) }
     fn gas_cost(&self) -> u64 { 8 }
 
-    fn exec(&self, mut ctx: Context) -> Context {
-        let a = U256::from(ctx.stack.pop().unwrap());
-        let b = U256::from(ctx.stack.pop().unwrap());
-        let c = U256::from(ctx.stack.pop().unwrap());
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let a = U256::from(ctx.stack.pop()?);
+        let b = U256::from(ctx.stack.pop()?);
+        let c = U256::from(ctx.stack.pop()?);
         if c.is_zero() {
-            ctx.stack.push(Word::from(U256::from(0)))
+            ctx.stack.push(Word::from(U256::from(0)))?
         } else {
             let d = a.overflowing_add(b).0;
-            ctx.stack.push(Word::from(d % c));
+            ctx.stack.push(Word::from(d % c))?;
         }
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpMulMod;
 
 impl OpcodeFn for OpMulMod {
+    fn stack_io(&self) -> (usize, usize) { (3, 1) }
     fn gas_cost(&self) -> u64 { 8 }
 
-    fn exec(&self, mut ctx: Context) -> Context {
-        let a = U256::from(ctx.stack.pop().unwrap());
-        let b = U256::from(ctx.stack.pop().unwrap());
-        let c = U256::from(ctx.stack.pop().unwrap());
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let a = U256::from(ctx.stack.pop()?);
+        let b = U256::from(ctx.stack.pop()?);
+        let c = U256::from(ctx.stack.pop()?);
         if c.is_zero() {
-            ctx.stack.push(Word::from(U256::from(0)))
+            ctx.stack.push(Word::from(U256::from(0)))?
         } else {
             let d = a.overflowing_mul(b).0;
-            ctx.stack.push(Word::from(d % c));
+            ctx.stack.push(Word::from(d % c))?;
         }
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpExp;
 
 impl OpcodeFn for OpExp {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 10 }
 
-    fn exec(&self, mut ctx: Context) -> Context {
-        let base = U256::from(ctx.stack.pop().unwrap());
-        let exponent = U256::from(ctx.stack.pop().unwrap());
-        ctx.stack.push(Word::from(base.overflowing_pow(exponent).0));
-        ctx.pc += 1;
+    fn dynamic_gas_cost(&self, ctx: &Context) -> Option<u64> {
+        let exponent = stack_peek(ctx, 1);
+        // the byte cost is raised from 10 in Spurious Dragon (EIP-160).
+        let byte_cost = if ctx.hardfork >= Hardfork::SpuriousDragon { 50 } else { 10 };
+        if exponent.is_zero() {
+            Some(0)
+        } else {
+            Some(byte_cost * exponent.actual_byte_size() as u64)
+        }
+    }
 
-        // additional gas cost
-        ctx.used_gas += 50 * exponent.actual_byte_size() as u64;
-        ctx
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let base = U256::from(ctx.stack.pop()?);
+        let exponent = U256::from(ctx.stack.pop()?);
+        ctx.stack.push(Word::from(base.overflowing_pow(exponent).0))?;
+        ctx.pc += 1;
+        Ok(())
     }
 }
 
 pub struct OpSignExtend;
 
 impl OpcodeFn for OpSignExtend {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 5 }
 
-    fn exec(&self, mut ctx: Context) -> Context {
-        let ext = U256::from(ctx.stack.pop().unwrap());
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let ext = U256::from(ctx.stack.pop()?);
         if ext < U256::from(31) {
-            let base = U256::from(ctx.stack.pop().unwrap());
+            let base = U256::from(ctx.stack.pop()?);
             let bit = ext * 8 + 7;
             let sign_mask = U256::from(1) << bit;
             let value_mask = sign_mask - 1;
             let is_neg = !(base & sign_mask).is_zero();
             if is_neg {
-                ctx.stack.push(Word::from(base | !value_mask));
+                ctx.stack.push(Word::from(base | !value_mask))?;
             } else {
-                ctx.stack.push(Word::from(base & value_mask));
+                ctx.stack.push(Word::from(base & value_mask))?;
             }
         }
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpLt;
 
 impl OpcodeFn for OpLt {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 3 }
 
-    fn exec(&self, mut ctx: Context) -> Context {
-        let a = U256::from(ctx.stack.pop().unwrap());
-        let b = U256::from(ctx.stack.pop().unwrap());
-        ctx.stack.push(Word::from(U256::from((a < b) as u8)));
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let a = U256::from(ctx.stack.pop()?);
+        let b = U256::from(ctx.stack.pop()?);
+        ctx.stack.push(Word::from(U256::from((a < b) as u8)))?;
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpGt;
 
 impl OpcodeFn for OpGt {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 3 }
 
-    fn exec(&self, mut ctx: Context) -> Context {
-        let a = U256::from(ctx.stack.pop().unwrap());
-        let b = U256::from(ctx.stack.pop().unwrap());
-        ctx.stack.push(Word::from(U256::from((a > b) as u8)));
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let a = U256::from(ctx.stack.pop()?);
+        let b = U256::from(ctx.stack.pop()?);
+        ctx.stack.push(Word::from(U256::from((a > b) as u8)))?;
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpSLt;
 
 impl OpcodeFn for OpSLt {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 3 }
-    fn exec(&self, mut ctx: Context) -> Context {
-        let a = U256::from(ctx.stack.pop().unwrap());
-        let b = U256::from(ctx.stack.pop().unwrap());
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let a = U256::from(ctx.stack.pop()?);
+        let b = U256::from(ctx.stack.pop()?);
         let neg_a = a.is_negative();
         let neg_b = b.is_negative();
         if neg_a ^ neg_b {
-            ctx.stack.push(Word::from(U256::from(neg_a as u8)));
+            ctx.stack.push(Word::from(U256::from(neg_a as u8)))?;
         } else {
-            ctx.stack.push(Word::from(U256::from((a < b) as u8)));
+            ctx.stack.push(Word::from(U256::from((a < b) as u8)))?;
         }
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpSGt;
 
 impl OpcodeFn for OpSGt {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 3 }
-    fn exec(&self, mut ctx: Context) -> Context {
-        let a = U256::from(ctx.stack.pop().unwrap());
-        let b = U256::from(ctx.stack.pop().unwrap());
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let a = U256::from(ctx.stack.pop()?);
+        let b = U256::from(ctx.stack.pop()?);
         let neg_a = a.is_negative();
         let neg_b = b.is_negative();
         if neg_a ^ neg_b {
-            ctx.stack.push(Word::from(U256::from(neg_b as u8)));
+            ctx.stack.push(Word::from(U256::from(neg_b as u8)))?;
         } else {
-            ctx.stack.push(Word::from(U256::from((a > b) as u8)));
+            ctx.stack.push(Word::from(U256::from((a > b) as u8)))?;
         }
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpEq;
 
 impl OpcodeFn for OpEq {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 3 }
-    fn exec(&self, mut ctx: Context) -> Context {
-        let a = U256::from(ctx.stack.pop().unwrap());
-        let b = U256::from(ctx.stack.pop().unwrap());
-        ctx.stack.push(Word::from(U256::from((a == b) as u8)));
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let a = U256::from(ctx.stack.pop()?);
+        let b = U256::from(ctx.stack.pop()?);
+        ctx.stack.push(Word::from(U256::from((a == b) as u8)))?;
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpIsZero;
 
 impl OpcodeFn for OpIsZero {
+    fn stack_io(&self) -> (usize, usize) { (1, 1) }
     fn gas_cost(&self) -> u64 { 3 }
-    fn exec(&self, mut ctx: Context) -> Context {
-        let a = U256::from(ctx.stack.pop().unwrap());
-        ctx.stack.push(Word::from(U256::from((a.is_zero()) as u8)));
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let a = U256::from(ctx.stack.pop()?);
+        ctx.stack.push(Word::from(U256::from((a.is_zero()) as u8)))?;
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpAnd;
 
 impl OpcodeFn for OpAnd {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 3 }
-    fn exec(&self, mut ctx: Context) -> Context {
-        let a = U256::from(ctx.stack.pop().unwrap());
-        let b = U256::from(ctx.stack.pop().unwrap());
-        ctx.stack.push(Word::from(a & b));
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let a = U256::from(ctx.stack.pop()?);
+        let b = U256::from(ctx.stack.pop()?);
+        ctx.stack.push(Word::from(a & b))?;
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpOr;
 
 impl OpcodeFn for OpOr {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 3 }
-    fn exec(&self, mut ctx: Context) -> Context {
-        let a = U256::from(ctx.stack.pop().unwrap());
-        let b = U256::from(ctx.stack.pop().unwrap());
-        ctx.stack.push(Word::from(a | b));
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let a = U256::from(ctx.stack.pop()?);
+        let b = U256::from(ctx.stack.pop()?);
+        ctx.stack.push(Word::from(a | b))?;
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpXOr;
 
 impl OpcodeFn for OpXOr {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 3 }
-    fn exec(&self, mut ctx: Context) -> Context {
-        let a = U256::from(ctx.stack.pop().unwrap());
-        let b = U256::from(ctx.stack.pop().unwrap());
-        ctx.stack.push(Word::from(a ^ b));
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let a = U256::from(ctx.stack.pop()?);
+        let b = U256::from(ctx.stack.pop()?);
+        ctx.stack.push(Word::from(a ^ b))?;
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpNot;
 
 impl OpcodeFn for OpNot {
+    fn stack_io(&self) -> (usize, usize) { (1, 1) }
     fn gas_cost(&self) -> u64 { 3 }
-    fn exec(&self, mut ctx: Context) -> Context {
-        let a = U256::from(ctx.stack.pop().unwrap());
-        ctx.stack.push(Word::from(!a));
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let a = U256::from(ctx.stack.pop()?);
+        ctx.stack.push(Word::from(!a))?;
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpByte;
 
 impl OpcodeFn for OpByte {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 3 }
-    fn exec(&self, mut ctx: Context) -> Context {
-        let n = U256::from(ctx.stack.pop().unwrap());
-        let x = U256::from(ctx.stack.pop().unwrap());
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let n = U256::from(ctx.stack.pop()?);
+        let x = U256::from(ctx.stack.pop()?);
         if n > U256::from(31_u8) {
-            ctx.stack.push(Word::from(U256::from(0_u8)));
+            ctx.stack.push(Word::from(U256::from(0_u8)))?;
         } else {
             let sh: u32 = (31 - n.low_u32()) * 8;
             let mut y = x >> sh;
             y = y & U256::from(0xff_u8);
-            ctx.stack.push(Word::from(y));
+            ctx.stack.push(Word::from(y))?;
         }
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpSHL;
 
 impl OpcodeFn for OpSHL {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 3 }
-    fn exec(&self, mut ctx: Context) -> Context {
-        let sh = U256::from(ctx.stack.pop().unwrap());
-        let x = U256::from(ctx.stack.pop().unwrap());
-        ctx.stack.push(Word::from(x << sh));
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let sh = U256::from(ctx.stack.pop()?);
+        let x = U256::from(ctx.stack.pop()?);
+        ctx.stack.push(Word::from(x << sh))?;
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpSHR;
 
 impl OpcodeFn for OpSHR {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 3 }
-    fn exec(&self, mut ctx: Context) -> Context {
-        let sh = U256::from(ctx.stack.pop().unwrap());
-        let x = U256::from(ctx.stack.pop().unwrap());
-        ctx.stack.push(Word::from(x >> sh));
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let sh = U256::from(ctx.stack.pop()?);
+        let x = U256::from(ctx.stack.pop()?);
+        ctx.stack.push(Word::from(x >> sh))?;
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpSAR;
 
 impl OpcodeFn for OpSAR {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 3 }
-    fn exec(&self, mut ctx: Context) -> Context {
-        let sh = U256::from(ctx.stack.pop().unwrap());
-        let x = U256::from(ctx.stack.pop().unwrap());
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let sh = U256::from(ctx.stack.pop()?);
+        let x = U256::from(ctx.stack.pop()?);
         let value_neg = x.is_negative();
         let u256_256 = U256::from(256);
         if !value_neg { // value is positive. it is same as right shift.
-            ctx.stack.push(Word::from(x >> sh));
+            ctx.stack.push(Word::from(x >> sh))?;
         } else { // keep top bit.
             let allones = !U256::from(0);
             if sh > u256_256 {
                 // cycled. so, all bit is 1.
-                ctx.stack.push(Word::from(allones));
+                ctx.stack.push(Word::from(allones))?;
             } else {
                 let y = (x >> sh) | (allones << (u256_256 - sh));
-                ctx.stack.push(Word::from(y));
+                ctx.stack.push(Word::from(y))?;
             }
         }
         ctx.pc += 1;
-        ctx
+        Ok(())
     }
 }
 
 pub struct OpSHA3;
 
 impl OpcodeFn for OpSHA3 {
+    fn stack_io(&self) -> (usize, usize) { (2, 1) }
     fn gas_cost(&self) -> u64 { 30 }
 
-    fn exec(&self, mut ctx: Context) -> Context {
-        let index = U256::from(ctx.stack.pop().unwrap());
-        let size = U256::from(ctx.stack.pop().unwrap());
-        match memory_allocation_check_u256(ctx, index, size) {
-            Ok(mut ctx) => {
-                let input = ctx.memory.read_multi_bytes(index.low_u64(), size.as_usize()).unwrap();
-                let hash = KeccakHasher::hash(&input);
-                ctx.stack.push(Word::from(&hash));
+    fn dynamic_gas_cost(&self, ctx: &Context) -> Option<u64> {
+        let index = stack_peek(ctx, 0);
+        let size = stack_peek(ctx, 1);
+        let memory_cost = memory_expansion_cost(ctx, index, size)?;
+        Some(memory_cost + word_size(size.as_usize()) as u64 * ctx.gas_table().sha3_word_cost)
+    }
 
-                // additional gas cost
-                ctx.used_gas += (word_size(size.as_usize()) * 6) as u64;
-                ctx.pc += 1;
-                ctx
-            },
-            Err(ctx) => ctx
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let index = U256::from(ctx.stack.pop()?);
+        let size = U256::from(ctx.stack.pop()?);
+        if memory_allocate(ctx, index, size).is_err() {
+            return Err(ContextState::Invalid);
         }
+        let input = ctx.memory.read_multi_bytes(index.low_u64(), size.as_usize()).unwrap();
+        let hash = KeccakHasher::hash(&input);
+        ctx.stack.push(Word::from(&hash))?;
+        ctx.pc += 1;
+        Ok(())
     }
 }
 
 // ###############################################################
-// #############          Memory Operations          #############
+// #############       Environmental Information     #############
 // ###############################################################
 
-fn memory_allocation_check_u256(ctx: Context, offset: U256, size: U256) -> InstructionResult {
-    if size > U256::from(std::u32::MAX) {
-        Err(out_of_gas(ctx))
-    } else {
-        memory_allocation_check(ctx, offset, size.as_usize())
+pub struct OpAddress;
+
+impl OpcodeFn for OpAddress {
+    fn stack_io(&self) -> (usize, usize) { (0, 1) }
+    fn gas_cost(&self) -> u64 { 2 }
+
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        ctx.stack.push(Word::from(&ctx.address))?;
+        ctx.pc += 1;
+        Ok(())
     }
 }
 
-fn memory_allocation_check(mut ctx: Context, offset: U256, size: usize) -> InstructionResult {
-    if offset > U256::from(std::u32::MAX) {
-        Err(out_of_gas(ctx))
-    } else {
-        let current_cost = ctx.memory.gas_cost();
-        match ctx.memory.allocate((offset + size).as_usize()) {
-            Ok(_) => {
-                let new_cost = ctx.memory.gas_cost();
-                // additional gas cost
-                ctx.used_gas += new_cost - current_cost;
-                if ctx.used_gas > ctx.remaining_gas {
-                    Err(out_of_gas(ctx))
-                } else {
-                    Ok(ctx)
-                }
-            },
-            Err(_) => {
-                Err(invalid(ctx))
-            }
-        }
+pub struct OpBalance;
+
+impl OpcodeFn for OpBalance {
+    fn stack_io(&self) -> (usize, usize) { (1, 1) }
+    fn gas_cost(&self) -> u64 { ACCOUNT_ACCESS_GAS }
+    fn hardfork_gas_cost(&self, hardfork: Hardfork) -> u64 { balance_cost(hardfork) }
+
+    fn state_gas_cost(&self, ctx: &Context, host: &dyn ContextInterface) -> Option<u64> {
+        Some(cold_account_cost(ctx, host, &peek_address(ctx, 0)))
+    }
+
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        let address = Address::from(ctx.stack.pop()?);
+        host.access_account(&address);
+        ctx.stack.push(Word::from(host.get_balance(&address)))?;
+        ctx.pc += 1;
+        Ok(())
     }
 }
 
-pub trait OpMemoryBase {
-    fn op_mem_exec(&self, mut ctx: Context) -> Context {
-        let offset = U256::from(ctx.stack.pop().unwrap());
-        let res = memory_allocation_check(ctx, offset, self.data_size());
-        match res {
-            Ok(mut ctx) => {
-                ctx = self.individual(offset.low_u64(), ctx);
-                ctx.pc += 1;
-                ctx
-            },
-            Err(ctx) => ctx,
-        }
+pub struct OpOrigin;
+
+impl OpcodeFn for OpOrigin {
+    fn stack_io(&self) -> (usize, usize) { (0, 1) }
+    fn gas_cost(&self) -> u64 { 2 }
+
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        ctx.stack.push(Word::from(&host.get_tx_context().origin))?;
+        ctx.pc += 1;
+        Ok(())
     }
-    fn data_size(&self) -> usize;
-    fn individual(&self, offset: u64, ctx: Context) -> Context;
 }
 
-pub struct OpMemoryFn<T: OpMemoryBase>(T);
+pub struct OpCaller;
 
-impl<T: OpMemoryBase> OpcodeFn for OpMemoryFn<T> {
-    fn gas_cost(&self) -> u64 { 3 }
+impl OpcodeFn for OpCaller {
+    fn stack_io(&self) -> (usize, usize) { (0, 1) }
+    fn gas_cost(&self) -> u64 { 2 }
 
-    fn exec(&self, ctx: Context) -> Context {
-        self.0.op_mem_exec(ctx)
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        ctx.stack.push(Word::from(&ctx.caller))?;
+        ctx.pc += 1;
+        Ok(())
     }
 }
 
-pub struct OpMLoad;
+pub struct OpCallValue;
 
-impl OpMemoryBase for OpMLoad {
-    fn data_size(&self) -> usize { Word::SIZE }
-    fn individual(&self, offset: u64, mut ctx: Context) -> Context {
-        ctx.stack.push(ctx.memory.read(offset).unwrap());
-        ctx
+impl OpcodeFn for OpCallValue {
+    fn stack_io(&self) -> (usize, usize) { (0, 1) }
+    fn gas_cost(&self) -> u64 { 2 }
+
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        ctx.stack.push(Word::from(ctx.value))?;
+        ctx.pc += 1;
+        Ok(())
     }
 }
 
-pub struct OpMStore;
+pub struct OpCallDataLoad;
 
-impl OpMemoryBase for OpMStore {
-    fn data_size(&self) -> usize { Word::SIZE }
-    fn individual(&self, offset: u64, mut ctx: Context) -> Context {
-        ctx.memory.write(offset, ctx.stack.pop().unwrap()).unwrap();
-        ctx
+impl OpcodeFn for OpCallDataLoad {
+    fn stack_io(&self) -> (usize, usize) { (1, 1) }
+    fn gas_cost(&self) -> u64 { 3 }
+
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let offset = U256::from(ctx.stack.pop()?);
+        let data = padded_slice(&ctx.input_data, offset, Word::SIZE);
+        ctx.stack.push(convert_word(&data, Word::SIZE))?;
+        ctx.pc += 1;
+        Ok(())
     }
 }
 
-pub struct OpMStore8;
+pub struct OpCallDataSize;
 
-impl OpMemoryBase for OpMStore8 {
-    fn data_size(&self) -> usize { 1 }
-    fn individual(&self, offset: u64, mut ctx: Context) -> Context {
-        let data = ctx.stack.pop().unwrap();
-        let onebyte: u8 = data.as_ref()[31];
-        ctx.memory.write(offset, &[onebyte]).unwrap();
-        ctx
+impl OpcodeFn for OpCallDataSize {
+    fn stack_io(&self) -> (usize, usize) { (0, 1) }
+    fn gas_cost(&self) -> u64 { 2 }
+
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        ctx.stack.push(Word::from(U256::from(ctx.input_data.len())))?;
+        ctx.pc += 1;
+        Ok(())
     }
 }
 
-pub struct OpInvalid;
+pub struct OpCallDataCopy;
 
-impl OpcodeFn for OpInvalid {
-    fn gas_cost(&self) -> u64 { 0 }
+impl OpcodeFn for OpCallDataCopy {
+    fn stack_io(&self) -> (usize, usize) { (3, 0) }
+    fn gas_cost(&self) -> u64 { 3 }
 
-    fn exec(&self, mut ctx: Context) -> Context {
-        ctx.pc = ctx.codes.len();
-        ctx
+    fn dynamic_gas_cost(&self, ctx: &Context) -> Option<u64> {
+        copy_gas_cost(ctx, stack_peek(ctx, 0), stack_peek(ctx, 2))
+    }
+
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        copy_exec(ctx, |ctx| &ctx.input_data)
     }
 }
 
-// ###############################################################
-// #############               OP_PUSH               #############
-// ###############################################################
+pub struct OpCodeSize;
 
-pub struct OpPush1;
+impl OpcodeFn for OpCodeSize {
+    fn stack_io(&self) -> (usize, usize) { (0, 1) }
+    fn gas_cost(&self) -> u64 { 2 }
 
-pub struct OpPush2;
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        ctx.stack.push(Word::from(U256::from(ctx.codes.len())))?;
+        ctx.pc += 1;
+        Ok(())
+    }
+}
 
-pub struct OpPush3;
+pub struct OpCodeCopy;
 
-pub struct OpPush4;
+impl OpcodeFn for OpCodeCopy {
+    fn stack_io(&self) -> (usize, usize) { (3, 0) }
+    fn gas_cost(&self) -> u64 { 3 }
 
-pub struct OpPush5;
+    fn dynamic_gas_cost(&self, ctx: &Context) -> Option<u64> {
+        copy_gas_cost(ctx, stack_peek(ctx, 0), stack_peek(ctx, 2))
+    }
 
-pub struct OpPush6;
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        copy_exec(ctx, |ctx| &ctx.codes)
+    }
+}
 
-pub struct OpPush7;
+pub struct OpGasPrice;
 
-pub struct OpPush8;
+impl OpcodeFn for OpGasPrice {
+    fn stack_io(&self) -> (usize, usize) { (0, 1) }
+    fn gas_cost(&self) -> u64 { 2 }
 
-pub struct OpPush9;
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        ctx.stack.push(Word::from(host.get_tx_context().gas_price))?;
+        ctx.pc += 1;
+        Ok(())
+    }
+}
 
-pub struct OpPush10;
+pub struct OpExtCodeSize;
 
-pub struct OpPush11;
+impl OpcodeFn for OpExtCodeSize {
+    fn stack_io(&self) -> (usize, usize) { (1, 1) }
+    fn gas_cost(&self) -> u64 { ACCOUNT_ACCESS_GAS }
+    fn hardfork_gas_cost(&self, hardfork: Hardfork) -> u64 { account_access_cost(hardfork, 20) }
 
-pub struct OpPush12;
+    fn state_gas_cost(&self, ctx: &Context, host: &dyn ContextInterface) -> Option<u64> {
+        Some(cold_account_cost(ctx, host, &peek_address(ctx, 0)))
+    }
 
-pub struct OpPush13;
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        let address = Address::from(ctx.stack.pop()?);
+        host.access_account(&address);
+        ctx.stack.push(Word::from(U256::from(host.get_code_size(&address))))?;
+        ctx.pc += 1;
+        Ok(())
+    }
+}
 
-pub struct OpPush14;
+pub struct OpExtCodeCopy;
 
-pub struct OpPush15;
+impl OpcodeFn for OpExtCodeCopy {
+    fn stack_io(&self) -> (usize, usize) { (4, 0) }
+    fn gas_cost(&self) -> u64 { ACCOUNT_ACCESS_GAS }
+    fn hardfork_gas_cost(&self, hardfork: Hardfork) -> u64 { account_access_cost(hardfork, 20) }
 
-pub struct OpPush16;
+    fn dynamic_gas_cost(&self, ctx: &Context) -> Option<u64> {
+        copy_gas_cost(ctx, stack_peek(ctx, 1), stack_peek(ctx, 3))
+    }
 
-pub struct OpPush17;
+    fn state_gas_cost(&self, ctx: &Context, host: &dyn ContextInterface) -> Option<u64> {
+        Some(cold_account_cost(ctx, host, &peek_address(ctx, 0)))
+    }
 
-pub struct OpPush18;
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        let address = Address::from(ctx.stack.pop()?);
+        host.access_account(&address);
+        let mem_offset = U256::from(ctx.stack.pop()?);
+        let code_offset = U256::from(ctx.stack.pop()?);
+        let size = U256::from(ctx.stack.pop()?);
+        if !size.is_zero() {
+            let mut code = vec![0; host.get_code_size(&address)];
+            host.copy_code(&address, &mut code);
+            let data = padded_slice(&code, code_offset, size.as_usize());
+            memory_store(ctx, mem_offset, &data)?;
+        }
+        ctx.pc += 1;
+        Ok(())
+    }
+}
 
-pub struct OpPush19;
+pub struct OpReturnDataSize;
 
-pub struct OpPush20;
+impl OpcodeFn for OpReturnDataSize {
+    fn stack_io(&self) -> (usize, usize) { (0, 1) }
+    fn gas_cost(&self) -> u64 { 2 }
 
-pub struct OpPush21;
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        ctx.stack.push(Word::from(U256::from(ctx.return_data_buffer.len())))?;
+        ctx.pc += 1;
+        Ok(())
+    }
+}
 
-pub struct OpPush22;
+/// unlike CALLDATACOPY, reading out of the buffer is an exceptional halt (EIP-211).
+pub struct OpReturnDataCopy;
 
-pub struct OpPush23;
+impl OpcodeFn for OpReturnDataCopy {
+    fn stack_io(&self) -> (usize, usize) { (3, 0) }
+    fn gas_cost(&self) -> u64 { 3 }
 
-pub struct OpPush24;
+    fn dynamic_gas_cost(&self, ctx: &Context) -> Option<u64> {
+        copy_gas_cost(ctx, stack_peek(ctx, 0), stack_peek(ctx, 2))
+    }
 
-pub struct OpPush25;
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let data_offset = stack_peek(ctx, 1);
+        let (end, overflow) = data_offset.overflowing_add(stack_peek(ctx, 2));
+        if overflow || end > U256::from(ctx.return_data_buffer.len()) {
+            return Err(ContextState::ReturnDataOutOfBounds);
+        }
+        copy_exec(ctx, |ctx| &ctx.return_data_buffer)
+    }
+}
 
-pub struct OpPush26;
+pub struct OpExtCodeHash;
 
-pub struct OpPush27;
+impl OpcodeFn for OpExtCodeHash {
+    fn stack_io(&self) -> (usize, usize) { (1, 1) }
+    fn gas_cost(&self) -> u64 { ACCOUNT_ACCESS_GAS }
+    fn hardfork_gas_cost(&self, hardfork: Hardfork) -> u64 { balance_cost(hardfork) }
 
-pub struct OpPush28;
+    fn state_gas_cost(&self, ctx: &Context, host: &dyn ContextInterface) -> Option<u64> {
+        Some(cold_account_cost(ctx, host, &peek_address(ctx, 0)))
+    }
 
-pub struct OpPush29;
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        let address = Address::from(ctx.stack.pop()?);
+        host.access_account(&address);
+        ctx.stack.push(host.get_code_hash(&address))?;
+        ctx.pc += 1;
+        Ok(())
+    }
+}
 
-pub struct OpPush30;
+// ###############################################################
+// #############          Block Information          #############
+// ###############################################################
 
-pub struct OpPush31;
+/// the number of the recent blocks whose hash is available.
+const BLOCKHASH_WINDOW: u64 = 256;
 
-pub struct OpPush32;
+pub struct OpBlockHash;
 
-pub trait OpPushGeneral {
-    fn data_size(&self) -> u8;
-    fn push_exec(&self, mut ctx: Context) -> Context {
-        let start = ctx.pc + 1;
-        let end = ctx.pc + 1 + self.data_size() as usize;
-        ctx.stack.push(convert_word(&ctx.codes[start..end], self.data_size() as usize));
-        ctx.pc += 1 + self.data_size() as usize;
-        ctx
+impl OpcodeFn for OpBlockHash {
+    fn stack_io(&self) -> (usize, usize) { (1, 1) }
+    fn gas_cost(&self) -> u64 { 20 }
+
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        let number = U256::from(ctx.stack.pop()?);
+        let current = U256::from(host.get_tx_context().block_number);
+        let hash = if number < current && current - number <= U256::from(BLOCKHASH_WINDOW) {
+            host.get_block_hash(number.low_u64())
+        } else {
+            Word::ZERO
+        };
+        ctx.stack.push(hash)?;
+        ctx.pc += 1;
+        Ok(())
     }
 }
 
-struct OpPushFn<T: OpPushGeneral>(T);
-impl<T: OpPushGeneral> OpcodeFn for OpPushFn<T> {
-    fn gas_cost(&self) -> u64 { 3 }
+pub struct OpCoinbase;
 
-    fn exec(&self, ctx: Context) -> Context {
-        self.0.push_exec(ctx)
+impl OpcodeFn for OpCoinbase {
+    fn stack_io(&self) -> (usize, usize) { (0, 1) }
+    fn gas_cost(&self) -> u64 { 2 }
+
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        ctx.stack.push(Word::from(&host.get_tx_context().block_coinbase))?;
+        ctx.pc += 1;
+        Ok(())
     }
 }
 
-impl OpPushGeneral for OpPush1 { fn data_size(&self) -> u8 { 1 } }
+pub struct OpTimestamp;
 
-impl OpPushGeneral for OpPush2 { fn data_size(&self) -> u8 { 2 } }
+impl OpcodeFn for OpTimestamp {
+    fn stack_io(&self) -> (usize, usize) { (0, 1) }
+    fn gas_cost(&self) -> u64 { 2 }
 
-impl OpPushGeneral for OpPush3 { fn data_size(&self) -> u8 { 3 } }
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        ctx.stack.push(Word::from(U256::from(host.get_tx_context().block_timestamp)))?;
+        ctx.pc += 1;
+        Ok(())
+    }
+}
 
-impl OpPushGeneral for OpPush4 { fn data_size(&self) -> u8 { 4 } }
+pub struct OpNumber;
 
-impl OpPushGeneral for OpPush5 { fn data_size(&self) -> u8 { 5 } }
+impl OpcodeFn for OpNumber {
+    fn stack_io(&self) -> (usize, usize) { (0, 1) }
+    fn gas_cost(&self) -> u64 { 2 }
 
-impl OpPushGeneral for OpPush6 { fn data_size(&self) -> u8 { 6 } }
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        ctx.stack.push(Word::from(U256::from(host.get_tx_context().block_number)))?;
+        ctx.pc += 1;
+        Ok(())
+    }
+}
 
-impl OpPushGeneral for OpPush7 { fn data_size(&self) -> u8 { 7 } }
+/// DIFFICULTY, or PREVRANDAO from Paris (EIP-4399).
+pub struct OpDifficulty;
 
-impl OpPushGeneral for OpPush8 { fn data_size(&self) -> u8 { 8 } }
+impl OpcodeFn for OpDifficulty {
+    fn stack_io(&self) -> (usize, usize) { (0, 1) }
+    fn gas_cost(&self) -> u64 { 2 }
 
-impl OpPushGeneral for OpPush9 { fn data_size(&self) -> u8 { 9 } }
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        let tx_context = host.get_tx_context();
+        let value = if ctx.hardfork >= Hardfork::Paris {
+            tx_context.block_prev_randao
+        } else {
+            tx_context.block_difficulty
+        };
+        ctx.stack.push(Word::from(value))?;
+        ctx.pc += 1;
+        Ok(())
+    }
+}
 
-impl OpPushGeneral for OpPush10 { fn data_size(&self) -> u8 { 10 } }
+pub struct OpGasLimit;
 
-impl OpPushGeneral for OpPush11 { fn data_size(&self) -> u8 { 11 } }
+impl OpcodeFn for OpGasLimit {
+    fn stack_io(&self) -> (usize, usize) { (0, 1) }
+    fn gas_cost(&self) -> u64 { 2 }
 
-impl OpPushGeneral for OpPush12 { fn data_size(&self) -> u8 { 12 } }
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        ctx.stack.push(Word::from(U256::from(host.get_tx_context().block_gas_limit)))?;
+        ctx.pc += 1;
+        Ok(())
+    }
+}
 
-impl OpPushGeneral for OpPush13 { fn data_size(&self) -> u8 { 13 } }
+pub struct OpChainId;
 
-impl OpPushGeneral for OpPush14 { fn data_size(&self) -> u8 { 14 } }
+impl OpcodeFn for OpChainId {
+    fn stack_io(&self) -> (usize, usize) { (0, 1) }
+    fn gas_cost(&self) -> u64 { 2 }
 
-impl OpPushGeneral for OpPush15 { fn data_size(&self) -> u8 { 15 } }
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        ctx.stack.push(Word::from(host.get_tx_context().chain_id))?;
+        ctx.pc += 1;
+        Ok(())
+    }
+}
 
-impl OpPushGeneral for OpPush16 { fn data_size(&self) -> u8 { 16 } }
+pub struct OpBaseFee;
 
-impl OpPushGeneral for OpPush17 { fn data_size(&self) -> u8 { 17 } }
+impl OpcodeFn for OpBaseFee {
+    fn stack_io(&self) -> (usize, usize) { (0, 1) }
+    fn gas_cost(&self) -> u64 { 2 }
 
-impl OpPushGeneral for OpPush18 { fn data_size(&self) -> u8 { 18 } }
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        ctx.stack.push(Word::from(host.get_tx_context().block_base_fee))?;
+        ctx.pc += 1;
+        Ok(())
+    }
+}
 
-impl OpPushGeneral for OpPush19 { fn data_size(&self) -> u8 { 19 } }
+// ###############################################################
+// #############          Memory Operations          #############
+// ###############################################################
 
-impl OpPushGeneral for OpPush20 { fn data_size(&self) -> u8 { 20 } }
+/// gas for expanding the memory to cover `[offset, offset + size)`.
+/// `None` means the range is too large to be paid.
+fn memory_expansion_cost(ctx: &Context, offset: U256, size: U256) -> Option<u64> {
+    if size.is_zero() {
+        return Some(0);
+    }
+    if offset > U256::from(std::u32::MAX) || size > U256::from(std::u32::MAX) {
+        return None;
+    }
+    Some(ctx.memory.expansion_cost((offset + size).as_usize(), ctx.gas_table()))
+}
 
-impl OpPushGeneral for OpPush21 { fn data_size(&self) -> u8 { 21 } }
+/// expand the memory. the gas should be charged by `memory_expansion_cost` before.
+fn memory_allocate(ctx: &mut Context, offset: U256, size: U256) -> std::io::Result<()> {
+    if size.is_zero() {
+        return Ok(());
+    }
+    ctx.memory.allocate((offset + size).as_usize())
+}
 
-impl OpPushGeneral for OpPush22 { fn data_size(&self) -> u8 { 22 } }
+/// gas for copying `size` bytes to the memory at `offset`. memory expansion plus the copy cost per word.
+fn copy_gas_cost(ctx: &Context, offset: U256, size: U256) -> Option<u64> {
+    let memory_cost = memory_expansion_cost(ctx, offset, size)?;
+    Some(memory_cost + word_size(size.as_usize()) as u64 * ctx.gas_table().copy_word_cost)
+}
 
-impl OpPushGeneral for OpPush23 { fn data_size(&self) -> u8 { 23 } }
+/// `size` bytes of `data` from `offset`. the bytes out of range are zero.
+fn padded_slice(data: &[u8], offset: U256, size: usize) -> Vec<u8> {
+    let mut buf = vec![0; size];
+    if offset < U256::from(data.len()) {
+        let start = offset.as_usize();
+        let end = std::cmp::min(start + size, data.len());
+        buf[..end - start].copy_from_slice(&data[start..end]);
+    }
+    buf
+}
 
-impl OpPushGeneral for OpPush24 { fn data_size(&self) -> u8 { 24 } }
+/// write `data` to the memory at `offset`, expanding the memory.
+fn memory_store(ctx: &mut Context, offset: U256, data: &[u8]) -> InstructionResult {
+    if data.is_empty() {
+        return Ok(());
+    }
+    if memory_allocate(ctx, offset, U256::from(data.len())).is_err() {
+        return Err(ContextState::Invalid);
+    }
+    ctx.memory.write(offset.low_u64(), data).unwrap();
+    Ok(())
+}
 
-impl OpPushGeneral for OpPush25 { fn data_size(&self) -> u8 { 25 } }
+/// pop `(memory offset, data offset, size)` and copy the data given by `source` to the memory.
+fn copy_exec(ctx: &mut Context, source: fn(&Context) -> &[u8]) -> InstructionResult {
+    let mem_offset = U256::from(ctx.stack.pop()?);
+    let data_offset = U256::from(ctx.stack.pop()?);
+    let size = U256::from(ctx.stack.pop()?);
+    if !size.is_zero() {
+        let data = padded_slice(source(ctx), data_offset, size.as_usize());
+        memory_store(ctx, mem_offset, &data)?;
+    }
+    ctx.pc += 1;
+    Ok(())
+}
 
-impl OpPushGeneral for OpPush26 { fn data_size(&self) -> u8 { 26 } }
+pub trait OpMemoryBase: Sync {
+    fn op_mem_exec(&self, ctx: &mut Context) -> InstructionResult {
+        let offset = U256::from(ctx.stack.pop()?);
+        if memory_allocate(ctx, offset, U256::from(self.data_size())).is_err() {
+            return Err(ContextState::Invalid);
+        }
+        self.individual(offset.low_u64(), ctx)?;
+        ctx.pc += 1;
+        Ok(())
+    }
+    fn stack_io(&self) -> (usize, usize);
+    fn data_size(&self) -> usize;
+    fn individual(&self, offset: u64, ctx: &mut Context) -> InstructionResult;
+}
 
-impl OpPushGeneral for OpPush27 { fn data_size(&self) -> u8 { 27 } }
+pub struct OpMemoryFn<T: OpMemoryBase>(T);
 
-impl OpPushGeneral for OpPush28 { fn data_size(&self) -> u8 { 28 } }
+impl<T: OpMemoryBase> OpcodeFn for OpMemoryFn<T> {
+    fn stack_io(&self) -> (usize, usize) { self.0.stack_io() }
+    fn gas_cost(&self) -> u64 { 3 }
 
-impl OpPushGeneral for OpPush29 { fn data_size(&self) -> u8 { 29 } }
+    fn dynamic_gas_cost(&self, ctx: &Context) -> Option<u64> {
+        memory_expansion_cost(ctx, stack_peek(ctx, 0), U256::from(self.0.data_size()))
+    }
 
-impl OpPushGeneral for OpPush30 { fn data_size(&self) -> u8 { 30 } }
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        self.0.op_mem_exec(ctx)
+    }
+}
 
-impl OpPushGeneral for OpPush31 { fn data_size(&self) -> u8 { 31 } }
+pub struct OpMLoad;
 
-impl OpPushGeneral for OpPush32 { fn data_size(&self) -> u8 { 32 } }
+impl OpMemoryBase for OpMLoad {
+    fn stack_io(&self) -> (usize, usize) { (1, 1) }
+    fn data_size(&self) -> usize { Word::SIZE }
+    fn individual(&self, offset: u64, ctx: &mut Context) -> InstructionResult {
+        let word = ctx.memory.read(offset).unwrap();
+        ctx.stack.push(word)?;
+        Ok(())
+    }
+}
 
-pub fn decode_op(opcode: u8) -> Box<dyn OpcodeFn> {
-    match opcode {
-        // Arithmetic
-        0x01 => Box::new(OpAdd),
-        0x02 => Box::new(OpMul),
-        0x03 => Box::new(OpSub),
-        0x04 => Box::new(OpDiv),
-        0x05 => Box::new(OpSDiv),
-        0x06 => Box::new(OpMod),
-        0x07 => Box::new(OpSMod),
-        0x08 => Box::new(OpAddMod),
-        0x09 => Box::new(OpMulMod),
-        0x0a => Box::new(OpExp),
-
-        // Bit extend
-        0x0b => Box::new(OpSignExtend),
-
-        // Compares
-        0x10 => Box::new(OpLt),
-        0x11 => Box::new(OpGt),
-        0x12 => Box::new(OpSLt),
-        0x13 => Box::new(OpSGt),
-        0x14 => Box::new(OpEq),
-        0x15 => Box::new(OpIsZero),
-
-        // Bitwise Operations
-        0x16 => Box::new(OpAnd),
-        0x17 => Box::new(OpOr),
-        0x18 => Box::new(OpXOr),
-        0x19 => Box::new(OpNot),
-        0x1a => Box::new(OpByte),
-        0x1b => Box::new(OpSHL),
-        0x1c => Box::new(OpSHR),
-        0x1d => Box::new(OpSAR),
-
-        0x20 => Box::new(OpSHA3),
-        // Memory Operations
-        0x51 => Box::new(OpMemoryFn(OpMLoad)),
-        0x52 => Box::new(OpMemoryFn(OpMStore)),
-        0x53 => Box::new(OpMemoryFn(OpMStore8)),
-
-        // PUSHx
-        0x60 => Box::new(OpPushFn(OpPush1)),
-        0x61 => Box::new(OpPushFn(OpPush2)),
-        0x62 => Box::new(OpPushFn(OpPush3)),
-        0x63 => Box::new(OpPushFn(OpPush4)),
-        0x64 => Box::new(OpPushFn(OpPush5)),
-        0x65 => Box::new(OpPushFn(OpPush6)),
-        0x66 => Box::new(OpPushFn(OpPush7)),
-        0x67 => Box::new(OpPushFn(OpPush8)),
-        0x68 => Box::new(OpPushFn(OpPush9)),
-        0x69 => Box::new(OpPushFn(OpPush10)),
-        0x6a => Box::new(OpPushFn(OpPush11)),
-        0x6b => Box::new(OpPushFn(OpPush12)),
-        0x6c => Box::new(OpPushFn(OpPush13)),
-        0x6d => Box::new(OpPushFn(OpPush14)),
-        0x6e => Box::new(OpPushFn(OpPush15)),
-        0x6f => Box::new(OpPushFn(OpPush16)),
-        0x70 => Box::new(OpPushFn(OpPush17)),
-        0x71 => Box::new(OpPushFn(OpPush18)),
-        0x72 => Box::new(OpPushFn(OpPush19)),
-        0x73 => Box::new(OpPushFn(OpPush20)),
-        0x74 => Box::new(OpPushFn(OpPush21)),
-        0x75 => Box::new(OpPushFn(OpPush22)),
-        0x76 => Box::new(OpPushFn(OpPush23)),
-        0x77 => Box::new(OpPushFn(OpPush24)),
-        0x78 => Box::new(OpPushFn(OpPush25)),
-        0x79 => Box::new(OpPushFn(OpPush26)),
-        0x7a => Box::new(OpPushFn(OpPush27)),
-        0x7b => Box::new(OpPushFn(OpPush28)),
-        0x7c => Box::new(OpPushFn(OpPush29)),
-        0x7d => Box::new(OpPushFn(OpPush30)),
-        0x7e => Box::new(OpPushFn(OpPush31)),
-        0x7f => Box::new(OpPushFn(OpPush32)),
-        _ => Box::new(OpInvalid),
-    }
-}
\ No newline at end of file
+pub struct OpMStore;
+
+impl OpMemoryBase for OpMStore {
+    fn stack_io(&self) -> (usize, usize) { (2, 0) }
+    fn data_size(&self) -> usize { Word::SIZE }
+    fn individual(&self, offset: u64, ctx: &mut Context) -> InstructionResult {
+        let word = ctx.stack.pop()?;
+        ctx.memory.write(offset, word).unwrap();
+        Ok(())
+    }
+}
+
+pub struct OpMStore8;
+
+impl OpMemoryBase for OpMStore8 {
+    fn stack_io(&self) -> (usize, usize) { (2, 0) }
+    fn data_size(&self) -> usize { 1 }
+    fn individual(&self, offset: u64, ctx: &mut Context) -> InstructionResult {
+        let data = ctx.stack.pop()?;
+        let onebyte: u8 = data.as_ref()[31];
+        ctx.memory.write(offset, &[onebyte]).unwrap();
+        Ok(())
+    }
+}
+
+// ###############################################################
+// #############          Storage Operations         #############
+// ###############################################################
+
+const SLOAD_GAS: u64 = 800;
+/// SSTORE fails if the gas left is not more than the call stipend (EIP-2200).
+const SSTORE_SENTRY_GAS: u64 = 2300;
+
+/// SLOAD is repriced in Tangerine Whistle (EIP-150), Istanbul (EIP-1884) and Berlin (EIP-2929).
+/// it is also the cost of SSTORE to the dirty slot with the net gas metering.
+fn sload_cost(hardfork: Hardfork) -> u64 {
+    match hardfork {
+        h if h >= Hardfork::Berlin => WARM_STORAGE_READ_COST,
+        h if h >= Hardfork::Istanbul => SLOAD_GAS,
+        h if h >= Hardfork::TangerineWhistle => 200,
+        _ => 50,
+    }
+}
+
+/// the net gas metering is in Constantinople (EIP-1283), removed in Petersburg and back in Istanbul (EIP-2200).
+fn is_net_gas_metering(hardfork: Hardfork) -> bool {
+    hardfork == Hardfork::Constantinople || hardfork >= Hardfork::Istanbul
+}
+
+fn sstore_clears_refund(hardfork: Hardfork, table: &GasTable) -> i64 {
+    match table.sstore_clears_refund {
+        Some(refund) => refund as i64,
+        None if hardfork >= Hardfork::London => 4800,
+        None => 15000,
+    }
+}
+
+/// (gas, refund) of SSTORE for the storage status.
+/// after Berlin, the read is charged as the warm access and the cold access is charged separately (EIP-2929).
+fn sstore_cost(status: StorageStatus, hardfork: Hardfork, table: &GasTable) -> (u64, i64) {
+    let clears = sstore_clears_refund(hardfork, table);
+    let sload = sload_cost(hardfork);
+    let set = table.sstore_set_cost;
+    let reset = if hardfork >= Hardfork::Berlin {
+        table.sstore_reset_cost.saturating_sub(table.cold_sload_cost)
+    } else {
+        table.sstore_reset_cost
+    };
+    match status {
+        StorageStatus::StorageUnchanged | StorageStatus::StorageModifiedAgain => (sload, 0),
+        StorageStatus::StorageAdded => (set, 0),
+        StorageStatus::StorageModified => (reset, 0),
+        StorageStatus::StorageDeleted => (reset, clears),
+        StorageStatus::StorageDeletedAdded => (sload, -clears),
+        StorageStatus::StorageModifiedDeleted => (sload, clears),
+        StorageStatus::StorageDeletedRestored => (sload, reset as i64 - sload as i64 - clears),
+        StorageStatus::StorageAddedDeleted => (sload, set as i64 - sload as i64),
+        StorageStatus::StorageModifiedRestored => (sload, reset as i64 - sload as i64),
+    }
+}
+
+/// (gas, refund) of SSTORE of `value` to `key` of the current account. the storage is not changed yet.
+fn sstore_gas(ctx: &Context, host: &dyn ContextInterface, key: &Word, value: &Word) -> (u64, i64) {
+    let current = host.get_storage(&ctx.address, key);
+    if is_net_gas_metering(ctx.hardfork) {
+        let original = host.get_original_storage(&ctx.address, key);
+        sstore_cost(StorageStatus::new(&original, &current, value), ctx.hardfork, ctx.gas_table())
+    } else {
+        legacy_sstore_cost(&current, value, ctx.hardfork, ctx.gas_table())
+    }
+}
+
+/// (gas, refund) of SSTORE without the net gas metering, decided by the current and new value.
+fn legacy_sstore_cost(current: &Word, value: &Word, hardfork: Hardfork, table: &GasTable) -> (u64, i64) {
+    if *current == Word::ZERO && *value != Word::ZERO {
+        (table.sstore_set_cost, 0)
+    } else if *current != Word::ZERO && *value == Word::ZERO {
+        (table.sstore_reset_cost, sstore_clears_refund(hardfork, table))
+    } else {
+        (table.sstore_reset_cost, 0)
+    }
+}
+
+pub struct OpSLoad;
+
+impl OpcodeFn for OpSLoad {
+    fn stack_io(&self) -> (usize, usize) { (1, 1) }
+    fn gas_cost(&self) -> u64 { SLOAD_GAS }
+    fn hardfork_gas_cost(&self, hardfork: Hardfork) -> u64 { sload_cost(hardfork) }
+
+    fn state_gas_cost(&self, ctx: &Context, host: &dyn ContextInterface) -> Option<u64> {
+        let key = Word::from(stack_peek(ctx, 0));
+        let cold = ctx.hardfork >= Hardfork::Berlin && host.storage_access_status(&ctx.address, &key) == AccessStatus::Cold;
+        Some(if cold { ctx.gas_table().cold_sload_cost.saturating_sub(WARM_STORAGE_READ_COST) } else { 0 })
+    }
+
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        let key = ctx.stack.pop()?;
+        host.access_storage(&ctx.address, &key);
+        ctx.stack.push(host.get_storage(&ctx.address, &key))?;
+        ctx.pc += 1;
+        Ok(())
+    }
+}
+
+/// the gas depends on the storage status, so it is decided from the original, current and new value.
+pub struct OpSStore;
+
+impl OpcodeFn for OpSStore {
+    fn stack_io(&self) -> (usize, usize) { (2, 0) }
+    fn gas_cost(&self) -> u64 { 0 }
+
+    fn state_gas_cost(&self, ctx: &Context, host: &dyn ContextInterface) -> Option<u64> {
+        if ctx.is_static {
+            // it fails in the execution.
+            return Some(0);
+        }
+        if ctx.hardfork >= Hardfork::Istanbul && ctx.gas_left() <= SSTORE_SENTRY_GAS {
+            return None;
+        }
+        let key = Word::from(stack_peek(ctx, 0));
+        let (cost, _) = sstore_gas(ctx, host, &key, &Word::from(stack_peek(ctx, 1)));
+        let cold = ctx.hardfork >= Hardfork::Berlin && host.storage_access_status(&ctx.address, &key) == AccessStatus::Cold;
+        Some(if cold { cost + ctx.gas_table().cold_sload_cost } else { cost })
+    }
+
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        if ctx.is_static {
+            return Err(ContextState::StaticModeViolation);
+        }
+        let key = ctx.stack.pop()?;
+        let value = ctx.stack.pop()?;
+        let (_, refund) = sstore_gas(ctx, host, &key, &value);
+        host.access_storage(&ctx.address, &key);
+        host.set_storage(&ctx.address, &key, &value);
+        ctx.refund_gas += refund;
+        ctx.pc += 1;
+        Ok(())
+    }
+}
+
+// ###############################################################
+// #############         Control Flow Operations     #############
+// ###############################################################
+
+fn jump_to(ctx: &mut Context, dest: U256) -> InstructionResult {
+    if dest > U256::from(ctx.codes.len()) || !ctx.jumpdests.is_valid(dest.as_usize()) {
+        return Err(ContextState::Invalid);
+    }
+    ctx.pc = dest.as_usize();
+    Ok(())
+}
+
+pub struct OpJump;
+
+impl OpcodeFn for OpJump {
+    fn stack_io(&self) -> (usize, usize) { (1, 0) }
+    fn gas_cost(&self) -> u64 { 8 }
+
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let dest = U256::from(ctx.stack.pop()?);
+        jump_to(ctx, dest)
+    }
+}
+
+pub struct OpJumpI;
+
+impl OpcodeFn for OpJumpI {
+    fn stack_io(&self) -> (usize, usize) { (2, 0) }
+    fn gas_cost(&self) -> u64 { 10 }
+
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        let dest = U256::from(ctx.stack.pop()?);
+        let condition = U256::from(ctx.stack.pop()?);
+        if condition.is_zero() {
+            ctx.pc += 1;
+            Ok(())
+        } else {
+            jump_to(ctx, dest)
+        }
+    }
+}
+
+pub struct OpPc;
+
+impl OpcodeFn for OpPc {
+    fn stack_io(&self) -> (usize, usize) { (0, 1) }
+    fn gas_cost(&self) -> u64 { 2 }
+
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        ctx.stack.push(Word::from(U256::from(ctx.pc)))?;
+        ctx.pc += 1;
+        Ok(())
+    }
+}
+
+pub struct OpJumpDest;
+
+impl OpcodeFn for OpJumpDest {
+    fn stack_io(&self) -> (usize, usize) { (0, 0) }
+    fn gas_cost(&self) -> u64 { 1 }
+
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        ctx.pc += 1;
+        Ok(())
+    }
+}
+
+// ###############################################################
+// #############          Logging Operations         #############
+// ###############################################################
+
+const LOG_GAS: u64 = 375;
+
+/// LOG0-LOG4. the field is the number of the topics.
+/// the static cost is the base, and the topics are charged by the gas table.
+pub struct OpLog(usize);
+
+impl OpcodeFn for OpLog {
+    fn stack_io(&self) -> (usize, usize) { (2 + self.0, 0) }
+    fn gas_cost(&self) -> u64 { LOG_GAS }
+
+    fn dynamic_gas_cost(&self, ctx: &Context) -> Option<u64> {
+        let size = stack_peek(ctx, 1);
+        let memory_cost = memory_expansion_cost(ctx, stack_peek(ctx, 0), size)?;
+        let topic_cost = self.0 as u64 * ctx.gas_table().log_topic_cost;
+        Some(memory_cost + topic_cost + size.low_u64() * ctx.gas_table().log_byte_cost)
+    }
+
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        if ctx.is_static {
+            return Err(ContextState::StaticModeViolation);
+        }
+        let offset = U256::from(ctx.stack.pop()?);
+        let size = U256::from(ctx.stack.pop()?);
+        let mut topics = Vec::with_capacity(self.0);
+        for _ in 0..self.0 {
+            topics.push(ctx.stack.pop()?);
+        }
+        let mut data = Vec::new();
+        if !size.is_zero() {
+            if memory_allocate(ctx, offset, size).is_err() {
+                return Err(ContextState::Invalid);
+            }
+            data = ctx.memory.read_multi_bytes(offset.low_u64(), size.as_usize()).unwrap();
+        }
+        host.emit_log(&ctx.address, &data, &topics);
+        ctx.pc += 1;
+        Ok(())
+    }
+}
+
+// ###############################################################
+// #############            Call Operations          #############
+// ###############################################################
+
+/// the gas given to the callee for free when the value is transferred.
+const CALL_STIPEND: u64 = 2300;
+
+/// memory expansion for the input and the output. `index` is the stack position of the input offset.
+fn call_memory_cost(ctx: &Context, index: usize) -> Option<u64> {
+    let input_cost = memory_expansion_cost(ctx, stack_peek(ctx, index), stack_peek(ctx, index + 1))?;
+    let output_cost = memory_expansion_cost(ctx, stack_peek(ctx, index + 2), stack_peek(ctx, index + 3))?;
+    Some(std::cmp::max(input_cost, output_cost))
+}
+
+/// the cold access and the new account cost of the callee, decided before the execution.
+fn call_state_gas_cost(ctx: &Context, host: &dyn ContextInterface, kind: CallKind) -> u64 {
+    let address = peek_address(ctx, 1);
+    let mut cost = cold_account_cost(ctx, host, &address);
+    let value = stack_peek(ctx, 2);
+    // the value in the static mode fails in the execution.
+    if kind == CallKind::Call && (!ctx.is_static || value.is_zero()) {
+        // the new account is charged only with the value after Spurious Dragon (EIP-161).
+        let charged = !value.is_zero() || ctx.hardfork < Hardfork::SpuriousDragon;
+        if charged && !host.account_exists(&address) {
+            cost += ctx.gas_table().call_new_account_cost;
+        }
+    }
+    cost
+}
+
+fn call_exec(ctx: &mut Context, host: &mut dyn ContextInterface, kind: CallKind) -> InstructionResult {
+    let gas = U256::from(ctx.stack.pop()?);
+    let address = Address::from(ctx.stack.pop()?);
+    let value = match kind {
+        CallKind::Call | CallKind::CallCode => U256::from(ctx.stack.pop()?),
+        _ => U256::zero(),
+    };
+    let in_offset = U256::from(ctx.stack.pop()?);
+    let in_size = U256::from(ctx.stack.pop()?);
+    let out_offset = U256::from(ctx.stack.pop()?);
+    let out_size = U256::from(ctx.stack.pop()?);
+
+    host.access_account(&address);
+    if kind == CallKind::Call && ctx.is_static && !value.is_zero() {
+        return Err(ContextState::StaticModeViolation);
+    }
+    if memory_allocate(ctx, in_offset, in_size).is_err() || memory_allocate(ctx, out_offset, out_size).is_err() {
+        return Err(ContextState::Invalid);
+    }
+    let input = if in_size.is_zero() {
+        Vec::new()
+    } else {
+        ctx.memory.read_multi_bytes(in_offset.low_u64(), in_size.as_usize()).unwrap()
+    };
+
+    // all but one 64th of the gas left can be given (EIP-150). before it, the requested gas must be paid.
+    let available = if ctx.hardfork >= Hardfork::TangerineWhistle {
+        ctx.gas_left() - ctx.gas_left() / 64
+    } else if gas > U256::from(ctx.gas_left()) {
+        return Err(ContextState::OutOfGas);
+    } else {
+        ctx.gas_left()
+    };
+    let mut call_gas = if gas > U256::from(available) { available } else { gas.low_u64() };
+    use_gas(ctx, call_gas)?;
+    if !value.is_zero() {
+        call_gas += CALL_STIPEND;
+    }
+
+    let message = CallMessage {
+        kind,
+        depth: ctx.depth + 1,
+        gas: call_gas as i64,
+        destination: match kind {
+            CallKind::Call | CallKind::StaticCall => address.clone(),
+            _ => ctx.address.clone(),
+        },
+        code_address: address,
+        sender: match kind {
+            CallKind::DelegateCall => ctx.caller.clone(),
+            _ => ctx.address.clone(),
+        },
+        input_data: &input,
+        value: match kind {
+            CallKind::DelegateCall => ctx.value,
+            _ => value,
+        },
+        create2_salt: Word::ZERO,
+        is_static: ctx.is_static || kind == CallKind::StaticCall,
+    };
+    let result = host.call(&message);
+
+    ctx.used_gas -= result.gas_left;
+    let size = std::cmp::min(out_size, U256::from(result.output.len())).as_usize();
+    if size > 0 {
+        ctx.memory.write(out_offset.low_u64(), &result.output[..size]).unwrap();
+    }
+    let success = result.state == ContextState::Success;
+    if success {
+        ctx.refund_gas += result.refund_gas;
+    }
+    ctx.return_data_buffer = result.output;
+    ctx.stack.push(Word::from(U256::from(success as u8)))?;
+    ctx.pc += 1;
+    Ok(())
+}
+
+pub struct OpCall;
+
+impl OpcodeFn for OpCall {
+    fn stack_io(&self) -> (usize, usize) { (7, 1) }
+    fn gas_cost(&self) -> u64 { ACCOUNT_ACCESS_GAS }
+    fn hardfork_gas_cost(&self, hardfork: Hardfork) -> u64 { account_access_cost(hardfork, 40) }
+
+    fn dynamic_gas_cost(&self, ctx: &Context) -> Option<u64> {
+        let value_cost = if stack_peek(ctx, 2).is_zero() { 0 } else { ctx.gas_table().call_value_cost };
+        Some(call_memory_cost(ctx, 3)? + value_cost)
+    }
+
+    fn state_gas_cost(&self, ctx: &Context, host: &dyn ContextInterface) -> Option<u64> {
+        Some(call_state_gas_cost(ctx, host, CallKind::Call))
+    }
+
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        call_exec(ctx, host, CallKind::Call)
+    }
+}
+
+/// call with the code of the other account, in the storage of the current account.
+pub struct OpCallCode;
+
+impl OpcodeFn for OpCallCode {
+    fn stack_io(&self) -> (usize, usize) { (7, 1) }
+    fn gas_cost(&self) -> u64 { ACCOUNT_ACCESS_GAS }
+    fn hardfork_gas_cost(&self, hardfork: Hardfork) -> u64 { account_access_cost(hardfork, 40) }
+
+    fn dynamic_gas_cost(&self, ctx: &Context) -> Option<u64> {
+        let value_cost = if stack_peek(ctx, 2).is_zero() { 0 } else { ctx.gas_table().call_value_cost };
+        Some(call_memory_cost(ctx, 3)? + value_cost)
+    }
+
+    fn state_gas_cost(&self, ctx: &Context, host: &dyn ContextInterface) -> Option<u64> {
+        Some(call_state_gas_cost(ctx, host, CallKind::CallCode))
+    }
+
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        call_exec(ctx, host, CallKind::CallCode)
+    }
+}
+
+/// same as CALLCODE, but the caller and the value of the current frame are kept.
+pub struct OpDelegateCall;
+
+impl OpcodeFn for OpDelegateCall {
+    fn stack_io(&self) -> (usize, usize) { (6, 1) }
+    fn gas_cost(&self) -> u64 { ACCOUNT_ACCESS_GAS }
+    fn hardfork_gas_cost(&self, hardfork: Hardfork) -> u64 { account_access_cost(hardfork, 40) }
+
+    fn dynamic_gas_cost(&self, ctx: &Context) -> Option<u64> {
+        call_memory_cost(ctx, 2)
+    }
+
+    fn state_gas_cost(&self, ctx: &Context, host: &dyn ContextInterface) -> Option<u64> {
+        Some(call_state_gas_cost(ctx, host, CallKind::DelegateCall))
+    }
+
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        call_exec(ctx, host, CallKind::DelegateCall)
+    }
+}
+
+/// call which can not modify the state.
+pub struct OpStaticCall;
+
+impl OpcodeFn for OpStaticCall {
+    fn stack_io(&self) -> (usize, usize) { (6, 1) }
+    fn gas_cost(&self) -> u64 { ACCOUNT_ACCESS_GAS }
+    fn hardfork_gas_cost(&self, hardfork: Hardfork) -> u64 { account_access_cost(hardfork, 40) }
+
+    fn dynamic_gas_cost(&self, ctx: &Context) -> Option<u64> {
+        call_memory_cost(ctx, 2)
+    }
+
+    fn state_gas_cost(&self, ctx: &Context, host: &dyn ContextInterface) -> Option<u64> {
+        Some(call_state_gas_cost(ctx, host, CallKind::StaticCall))
+    }
+
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        call_exec(ctx, host, CallKind::StaticCall)
+    }
+}
+
+// ###############################################################
+// #############          Create Operations          #############
+// ###############################################################
+
+const CREATE_GAS: u64 = 32000;
+
+fn create_exec(ctx: &mut Context, host: &mut dyn ContextInterface, kind: CallKind) -> InstructionResult {
+    if ctx.is_static {
+        return Err(ContextState::StaticModeViolation);
+    }
+    let value = U256::from(ctx.stack.pop()?);
+    let offset = U256::from(ctx.stack.pop()?);
+    let size = U256::from(ctx.stack.pop()?);
+    let salt = match kind {
+        CallKind::Create2 => ctx.stack.pop()?,
+        _ => Word::ZERO,
+    };
+    let init_code = if size.is_zero() {
+        Vec::new()
+    } else {
+        if memory_allocate(ctx, offset, size).is_err() {
+            return Err(ContextState::Invalid);
+        }
+        ctx.memory.read_multi_bytes(offset.low_u64(), size.as_usize()).unwrap()
+    };
+
+    // all but one 64th of the gas left is given (EIP-150).
+    let create_gas = if ctx.hardfork >= Hardfork::TangerineWhistle {
+        ctx.gas_left() - ctx.gas_left() / 64
+    } else {
+        ctx.gas_left()
+    };
+    use_gas(ctx, create_gas)?;
+    let message = CallMessage {
+        kind,
+        depth: ctx.depth + 1,
+        gas: create_gas as i64,
+        destination: Address::default(),
+        code_address: Address::default(),
+        sender: ctx.address.clone(),
+        input_data: &init_code,
+        value,
+        create2_salt: salt,
+        is_static: false,
+    };
+    let result = host.call(&message);
+
+    ctx.used_gas -= result.gas_left;
+    // the output is empty unless the init code is reverted.
+    ctx.return_data_buffer = result.output;
+    if result.state == ContextState::Success {
+        ctx.refund_gas += result.refund_gas;
+        ctx.stack.push(Word::from(&result.create_address))?;
+    } else {
+        ctx.stack.push(Word::ZERO)?;
+    }
+    ctx.pc += 1;
+    Ok(())
+}
+
+pub struct OpCreate;
+
+impl OpcodeFn for OpCreate {
+    fn stack_io(&self) -> (usize, usize) { (3, 1) }
+    fn gas_cost(&self) -> u64 { CREATE_GAS }
+
+    fn dynamic_gas_cost(&self, ctx: &Context) -> Option<u64> {
+        memory_expansion_cost(ctx, stack_peek(ctx, 1), stack_peek(ctx, 2))
+    }
+
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        create_exec(ctx, host, CallKind::Create)
+    }
+}
+
+/// the address is decided by the salt and the init code, not by the nonce.
+pub struct OpCreate2;
+
+impl OpcodeFn for OpCreate2 {
+    fn stack_io(&self) -> (usize, usize) { (4, 1) }
+    fn gas_cost(&self) -> u64 { CREATE_GAS }
+
+    /// memory expansion plus the SHA3 word cost for hashing the init code.
+    fn dynamic_gas_cost(&self, ctx: &Context) -> Option<u64> {
+        let size = stack_peek(ctx, 2);
+        let memory_cost = memory_expansion_cost(ctx, stack_peek(ctx, 1), size)?;
+        Some(memory_cost + word_size(size.as_usize()) as u64 * ctx.gas_table().sha3_word_cost)
+    }
+
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        create_exec(ctx, host, CallKind::Create2)
+    }
+}
+
+// ###############################################################
+// #############          Halting Operations         #############
+// ###############################################################
+
+pub struct OpStop;
+
+impl OpcodeFn for OpStop {
+    fn stack_io(&self) -> (usize, usize) { (0, 0) }
+    fn gas_cost(&self) -> u64 { 0 }
+
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        halt(ctx, ContextState::Success);
+        Ok(())
+    }
+}
+
+fn return_gas_cost(ctx: &Context) -> Option<u64> {
+    memory_expansion_cost(ctx, stack_peek(ctx, 0), stack_peek(ctx, 1))
+}
+
+fn return_exec(ctx: &mut Context, state: ContextState) -> InstructionResult {
+    let offset = U256::from(ctx.stack.pop()?);
+    let size = U256::from(ctx.stack.pop()?);
+    if !size.is_zero() {
+        if memory_allocate(ctx, offset, size).is_err() {
+            return Err(ContextState::Invalid);
+        }
+        ctx.return_data = ctx.memory.read_multi_bytes(offset.low_u64(), size.as_usize()).unwrap();
+    }
+    halt(ctx, state);
+    Ok(())
+}
+
+pub struct OpReturn;
+
+impl OpcodeFn for OpReturn {
+    fn stack_io(&self) -> (usize, usize) { (2, 0) }
+    fn gas_cost(&self) -> u64 { 0 }
+
+    fn dynamic_gas_cost(&self, ctx: &Context) -> Option<u64> {
+        return_gas_cost(ctx)
+    }
+
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        return_exec(ctx, ContextState::Success)
+    }
+}
+
+pub struct OpRevert;
+
+impl OpcodeFn for OpRevert {
+    fn stack_io(&self) -> (usize, usize) { (2, 0) }
+    fn gas_cost(&self) -> u64 { 0 }
+
+    fn dynamic_gas_cost(&self, ctx: &Context) -> Option<u64> {
+        return_gas_cost(ctx)
+    }
+
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        return_exec(ctx, ContextState::Revert)
+    }
+}
+
+const SELFDESTRUCT_GAS: u64 = 5000;
+const SELFDESTRUCT_REFUND: i64 = 24000;
+
+/// move the balance to the beneficiary and halt.
+/// after Cancun, the account is deleted only if it is created in the same transaction (EIP-6780).
+pub struct OpSelfDestruct;
+
+impl OpcodeFn for OpSelfDestruct {
+    fn stack_io(&self) -> (usize, usize) { (1, 0) }
+    fn gas_cost(&self) -> u64 { SELFDESTRUCT_GAS }
+
+    fn hardfork_gas_cost(&self, hardfork: Hardfork) -> u64 {
+        if hardfork >= Hardfork::TangerineWhistle { SELFDESTRUCT_GAS } else { 0 }
+    }
+
+    fn state_gas_cost(&self, ctx: &Context, host: &dyn ContextInterface) -> Option<u64> {
+        if ctx.is_static {
+            // it fails in the execution.
+            return Some(0);
+        }
+        let beneficiary = peek_address(ctx, 0);
+        let mut cost = 0;
+        if ctx.hardfork >= Hardfork::Berlin && host.account_access_status(&beneficiary) == AccessStatus::Cold {
+            cost += ctx.gas_table().cold_account_access_cost;
+        }
+        // the new account is charged from Tangerine Whistle, and only with the balance after Spurious Dragon (EIP-161).
+        let charged = match ctx.hardfork {
+            h if h >= Hardfork::SpuriousDragon => !host.get_balance(&ctx.address).is_zero(),
+            h => h >= Hardfork::TangerineWhistle,
+        };
+        if charged && !host.account_exists(&beneficiary) {
+            cost += ctx.gas_table().selfdestruct_new_account_cost;
+        }
+        Some(cost)
+    }
+
+    fn exec(&self, ctx: &mut Context, host: &mut dyn ContextInterface) -> InstructionResult {
+        if ctx.is_static {
+            return Err(ContextState::StaticModeViolation);
+        }
+        let beneficiary = Address::from(ctx.stack.pop()?);
+        host.access_account(&beneficiary);
+        let balance = host.get_balance(&ctx.address);
+        if ctx.hardfork >= Hardfork::Cancun && !host.is_created(&ctx.address) {
+            host.transfer(&ctx.address, &beneficiary, balance);
+        } else if host.selfdestruct(&ctx.address, &beneficiary) && ctx.hardfork < Hardfork::London {
+            // the refund is removed by EIP-3529.
+            ctx.refund_gas += SELFDESTRUCT_REFUND;
+        }
+        halt(ctx, ContextState::Success);
+        Ok(())
+    }
+}
+
+pub struct OpInvalid;
+
+impl OpcodeFn for OpInvalid {
+    fn stack_io(&self) -> (usize, usize) { (0, 0) }
+    fn gas_cost(&self) -> u64 { 0 }
+
+    fn exec(&self, _ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        Err(ContextState::Invalid)
+    }
+}
+
+// ###############################################################
+// #############               OP_PUSH               #############
+// ###############################################################
+
+pub struct OpPush1;
+
+pub struct OpPush2;
+
+pub struct OpPush3;
+
+pub struct OpPush4;
+
+pub struct OpPush5;
+
+pub struct OpPush6;
+
+pub struct OpPush7;
+
+pub struct OpPush8;
+
+pub struct OpPush9;
+
+pub struct OpPush10;
+
+pub struct OpPush11;
+
+pub struct OpPush12;
+
+pub struct OpPush13;
+
+pub struct OpPush14;
+
+pub struct OpPush15;
+
+pub struct OpPush16;
+
+pub struct OpPush17;
+
+pub struct OpPush18;
+
+pub struct OpPush19;
+
+pub struct OpPush20;
+
+pub struct OpPush21;
+
+pub struct OpPush22;
+
+pub struct OpPush23;
+
+pub struct OpPush24;
+
+pub struct OpPush25;
+
+pub struct OpPush26;
+
+pub struct OpPush27;
+
+pub struct OpPush28;
+
+pub struct OpPush29;
+
+pub struct OpPush30;
+
+pub struct OpPush31;
+
+pub struct OpPush32;
+
+pub trait OpPushGeneral: Sync {
+    fn data_size(&self) -> u8;
+    fn push_exec(&self, ctx: &mut Context) -> InstructionResult {
+        let start = ctx.pc + 1;
+        let end = ctx.pc + 1 + self.data_size() as usize;
+        // the data beyond the end of the code is regarded as zero.
+        let data = &ctx.codes[start.min(ctx.codes.len())..end.min(ctx.codes.len())];
+        ctx.stack.push(convert_word(data, self.data_size() as usize))?;
+        ctx.pc += 1 + self.data_size() as usize;
+        Ok(())
+    }
+}
+
+struct OpPushFn<T: OpPushGeneral>(T);
+impl<T: OpPushGeneral> OpcodeFn for OpPushFn<T> {
+    fn stack_io(&self) -> (usize, usize) { (0, 1) }
+    fn gas_cost(&self) -> u64 { 3 }
+
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        self.0.push_exec(ctx)
+    }
+}
+
+impl OpPushGeneral for OpPush1 { fn data_size(&self) -> u8 { 1 } }
+
+impl OpPushGeneral for OpPush2 { fn data_size(&self) -> u8 { 2 } }
+
+impl OpPushGeneral for OpPush3 { fn data_size(&self) -> u8 { 3 } }
+
+impl OpPushGeneral for OpPush4 { fn data_size(&self) -> u8 { 4 } }
+
+impl OpPushGeneral for OpPush5 { fn data_size(&self) -> u8 { 5 } }
+
+impl OpPushGeneral for OpPush6 { fn data_size(&self) -> u8 { 6 } }
+
+impl OpPushGeneral for OpPush7 { fn data_size(&self) -> u8 { 7 } }
+
+impl OpPushGeneral for OpPush8 { fn data_size(&self) -> u8 { 8 } }
+
+impl OpPushGeneral for OpPush9 { fn data_size(&self) -> u8 { 9 } }
+
+impl OpPushGeneral for OpPush10 { fn data_size(&self) -> u8 { 10 } }
+
+impl OpPushGeneral for OpPush11 { fn data_size(&self) -> u8 { 11 } }
+
+impl OpPushGeneral for OpPush12 { fn data_size(&self) -> u8 { 12 } }
+
+impl OpPushGeneral for OpPush13 { fn data_size(&self) -> u8 { 13 } }
+
+impl OpPushGeneral for OpPush14 { fn data_size(&self) -> u8 { 14 } }
+
+impl OpPushGeneral for OpPush15 { fn data_size(&self) -> u8 { 15 } }
+
+impl OpPushGeneral for OpPush16 { fn data_size(&self) -> u8 { 16 } }
+
+impl OpPushGeneral for OpPush17 { fn data_size(&self) -> u8 { 17 } }
+
+impl OpPushGeneral for OpPush18 { fn data_size(&self) -> u8 { 18 } }
+
+impl OpPushGeneral for OpPush19 { fn data_size(&self) -> u8 { 19 } }
+
+impl OpPushGeneral for OpPush20 { fn data_size(&self) -> u8 { 20 } }
+
+impl OpPushGeneral for OpPush21 { fn data_size(&self) -> u8 { 21 } }
+
+impl OpPushGeneral for OpPush22 { fn data_size(&self) -> u8 { 22 } }
+
+impl OpPushGeneral for OpPush23 { fn data_size(&self) -> u8 { 23 } }
+
+impl OpPushGeneral for OpPush24 { fn data_size(&self) -> u8 { 24 } }
+
+impl OpPushGeneral for OpPush25 { fn data_size(&self) -> u8 { 25 } }
+
+impl OpPushGeneral for OpPush26 { fn data_size(&self) -> u8 { 26 } }
+
+impl OpPushGeneral for OpPush27 { fn data_size(&self) -> u8 { 27 } }
+
+impl OpPushGeneral for OpPush28 { fn data_size(&self) -> u8 { 28 } }
+
+impl OpPushGeneral for OpPush29 { fn data_size(&self) -> u8 { 29 } }
+
+impl OpPushGeneral for OpPush30 { fn data_size(&self) -> u8 { 30 } }
+
+impl OpPushGeneral for OpPush31 { fn data_size(&self) -> u8 { 31 } }
+
+impl OpPushGeneral for OpPush32 { fn data_size(&self) -> u8 { 32 } }
+
+// ###############################################################
+// #############            OP_POP / OP_DUP          #############
+// ###############################################################
+
+pub struct OpPop;
+
+impl OpcodeFn for OpPop {
+    fn stack_io(&self) -> (usize, usize) { (1, 0) }
+    fn gas_cost(&self) -> u64 { 2 }
+
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        ctx.stack.pop()?;
+        ctx.pc += 1;
+        Ok(())
+    }
+}
+
+pub struct OpDup1;
+
+pub struct OpDup2;
+
+pub struct OpDup3;
+
+pub struct OpDup4;
+
+pub struct OpDup5;
+
+pub struct OpDup6;
+
+pub struct OpDup7;
+
+pub struct OpDup8;
+
+pub struct OpDup9;
+
+pub struct OpDup10;
+
+pub struct OpDup11;
+
+pub struct OpDup12;
+
+pub struct OpDup13;
+
+pub struct OpDup14;
+
+pub struct OpDup15;
+
+pub struct OpDup16;
+
+pub trait OpDupGeneral: Sync {
+    fn position(&self) -> usize;
+    fn dup_exec(&self, ctx: &mut Context) -> InstructionResult {
+        ctx.stack.dup(self.position())?;
+        ctx.pc += 1;
+        Ok(())
+    }
+}
+
+struct OpDupFn<T: OpDupGeneral>(T);
+impl<T: OpDupGeneral> OpcodeFn for OpDupFn<T> {
+    fn stack_io(&self) -> (usize, usize) { (self.0.position(), self.0.position() + 1) }
+    fn gas_cost(&self) -> u64 { 3 }
+
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        self.0.dup_exec(ctx)
+    }
+}
+
+impl OpDupGeneral for OpDup1 { fn position(&self) -> usize { 1 } }
+
+impl OpDupGeneral for OpDup2 { fn position(&self) -> usize { 2 } }
+
+impl OpDupGeneral for OpDup3 { fn position(&self) -> usize { 3 } }
+
+impl OpDupGeneral for OpDup4 { fn position(&self) -> usize { 4 } }
+
+impl OpDupGeneral for OpDup5 { fn position(&self) -> usize { 5 } }
+
+impl OpDupGeneral for OpDup6 { fn position(&self) -> usize { 6 } }
+
+impl OpDupGeneral for OpDup7 { fn position(&self) -> usize { 7 } }
+
+impl OpDupGeneral for OpDup8 { fn position(&self) -> usize { 8 } }
+
+impl OpDupGeneral for OpDup9 { fn position(&self) -> usize { 9 } }
+
+impl OpDupGeneral for OpDup10 { fn position(&self) -> usize { 10 } }
+
+impl OpDupGeneral for OpDup11 { fn position(&self) -> usize { 11 } }
+
+impl OpDupGeneral for OpDup12 { fn position(&self) -> usize { 12 } }
+
+impl OpDupGeneral for OpDup13 { fn position(&self) -> usize { 13 } }
+
+impl OpDupGeneral for OpDup14 { fn position(&self) -> usize { 14 } }
+
+impl OpDupGeneral for OpDup15 { fn position(&self) -> usize { 15 } }
+
+impl OpDupGeneral for OpDup16 { fn position(&self) -> usize { 16 } }
+
+// ###############################################################
+// #############               OP_SWAP               #############
+// ###############################################################
+
+pub struct OpSwap1;
+
+pub struct OpSwap2;
+
+pub struct OpSwap3;
+
+pub struct OpSwap4;
+
+pub struct OpSwap5;
+
+pub struct OpSwap6;
+
+pub struct OpSwap7;
+
+pub struct OpSwap8;
+
+pub struct OpSwap9;
+
+pub struct OpSwap10;
+
+pub struct OpSwap11;
+
+pub struct OpSwap12;
+
+pub struct OpSwap13;
+
+pub struct OpSwap14;
+
+pub struct OpSwap15;
+
+pub struct OpSwap16;
+
+pub trait OpSwapGeneral: Sync {
+    fn position(&self) -> usize;
+    fn swap_exec(&self, ctx: &mut Context) -> InstructionResult {
+        ctx.stack.swap(self.position())?;
+        ctx.pc += 1;
+        Ok(())
+    }
+}
+
+struct OpSwapFn<T: OpSwapGeneral>(T);
+impl<T: OpSwapGeneral> OpcodeFn for OpSwapFn<T> {
+    fn stack_io(&self) -> (usize, usize) { (self.0.position() + 1, self.0.position() + 1) }
+    fn gas_cost(&self) -> u64 { 3 }
+
+    fn exec(&self, ctx: &mut Context, _host: &mut dyn ContextInterface) -> InstructionResult {
+        self.0.swap_exec(ctx)
+    }
+}
+
+impl OpSwapGeneral for OpSwap1 { fn position(&self) -> usize { 1 } }
+
+impl OpSwapGeneral for OpSwap2 { fn position(&self) -> usize { 2 } }
+
+impl OpSwapGeneral for OpSwap3 { fn position(&self) -> usize { 3 } }
+
+impl OpSwapGeneral for OpSwap4 { fn position(&self) -> usize { 4 } }
+
+impl OpSwapGeneral for OpSwap5 { fn position(&self) -> usize { 5 } }
+
+impl OpSwapGeneral for OpSwap6 { fn position(&self) -> usize { 6 } }
+
+impl OpSwapGeneral for OpSwap7 { fn position(&self) -> usize { 7 } }
+
+impl OpSwapGeneral for OpSwap8 { fn position(&self) -> usize { 8 } }
+
+impl OpSwapGeneral for OpSwap9 { fn position(&self) -> usize { 9 } }
+
+impl OpSwapGeneral for OpSwap10 { fn position(&self) -> usize { 10 } }
+
+impl OpSwapGeneral for OpSwap11 { fn position(&self) -> usize { 11 } }
+
+impl OpSwapGeneral for OpSwap12 { fn position(&self) -> usize { 12 } }
+
+impl OpSwapGeneral for OpSwap13 { fn position(&self) -> usize { 13 } }
+
+impl OpSwapGeneral for OpSwap14 { fn position(&self) -> usize { 14 } }
+
+impl OpSwapGeneral for OpSwap15 { fn position(&self) -> usize { 15 } }
+
+impl OpSwapGeneral for OpSwap16 { fn position(&self) -> usize { 16 } }
+
+// ###############################################################
+// #############             Jump Table              #############
+// ###############################################################
+
+/// opcode to instruction table. the undefined opcodes are `OpInvalid`.
+/// a custom instruction can be installed by `set`, and a custom gas schedule by `set_gas_table`.
+#[derive(Clone)]
+pub struct JumpTable {
+    ops: [&'static dyn OpcodeFn; 256],
+    gas_table: Option<Arc<GasTable>>,
+    /// the static costs of the gas table by the opcode, resolved in `set_gas_table`.
+    static_costs: [Option<u64>; 256],
+}
+
+impl JumpTable {
+    pub fn get(&self, opcode: u8) -> &'static dyn OpcodeFn {
+        self.ops[opcode as usize]
+    }
+
+    pub fn set(&mut self, opcode: u8, op: &'static dyn OpcodeFn) {
+        self.ops[opcode as usize] = op;
+    }
+
+    /// the custom gas schedule. `None` is the built-in one.
+    pub fn gas_table(&self) -> Option<&Arc<GasTable>> {
+        self.gas_table.as_ref()
+    }
+
+    /// replace the built-in costs with the gas table, in all the frames run with this table.
+    /// the mnemonics in `static_costs` are checked when the gas table is loaded from JSON.
+    /// the unknown ones in the gas table built in the code are ignored.
+    pub fn set_gas_table(&mut self, gas_table: GasTable) {
+        self.static_costs = [None; 256];
+        for (name, cost) in &gas_table.static_costs {
+            if let Some(opcode) = opcode_from_name(name) {
+                self.static_costs[opcode as usize] = Some(*cost);
+            }
+        }
+        self.gas_table = Some(Arc::new(gas_table));
+    }
+
+    /// the instruction on the hardfork. the opcodes introduced after the hardfork are `OpInvalid`.
+    pub fn decode(&self, opcode: u8, hardfork: Hardfork) -> &'static dyn OpcodeFn {
+        if hardfork < opcode_hardfork(opcode) {
+            &OpInvalid
+        } else {
+            self.get(opcode)
+        }
+    }
+}
+
+impl Default for JumpTable {
+    fn default() -> Self {
+        JUMP_TABLE.clone()
+    }
+}
+
+pub static JUMP_TABLE: JumpTable = {
+    let mut table: [&'static dyn OpcodeFn; 256] = [&OpInvalid; 256];
+    table[0x00] = &OpStop;
+
+    // Arithmetic
+    table[0x01] = &OpAdd;
+    table[0x02] = &OpMul;
+    table[0x03] = &OpSub;
+    table[0x04] = &OpDiv;
+    table[0x05] = &OpSDiv;
+    table[0x06] = &OpMod;
+    table[0x07] = &OpSMod;
+    table[0x08] = &OpAddMod;
+    table[0x09] = &OpMulMod;
+    table[0x0a] = &OpExp;
+
+    // Bit extend
+    table[0x0b] = &OpSignExtend;
+
+    // Compares
+    table[0x10] = &OpLt;
+    table[0x11] = &OpGt;
+    table[0x12] = &OpSLt;
+    table[0x13] = &OpSGt;
+    table[0x14] = &OpEq;
+    table[0x15] = &OpIsZero;
+
+    // Bitwise Operations
+    table[0x16] = &OpAnd;
+    table[0x17] = &OpOr;
+    table[0x18] = &OpXOr;
+    table[0x19] = &OpNot;
+    table[0x1a] = &OpByte;
+    table[0x1b] = &OpSHL;
+    table[0x1c] = &OpSHR;
+    table[0x1d] = &OpSAR;
+
+    table[0x20] = &OpSHA3;
+
+    // Environmental Information
+    table[0x30] = &OpAddress;
+    table[0x31] = &OpBalance;
+    table[0x32] = &OpOrigin;
+    table[0x33] = &OpCaller;
+    table[0x34] = &OpCallValue;
+    table[0x35] = &OpCallDataLoad;
+    table[0x36] = &OpCallDataSize;
+    table[0x37] = &OpCallDataCopy;
+    table[0x38] = &OpCodeSize;
+    table[0x39] = &OpCodeCopy;
+    table[0x3a] = &OpGasPrice;
+    table[0x3b] = &OpExtCodeSize;
+    table[0x3c] = &OpExtCodeCopy;
+    table[0x3d] = &OpReturnDataSize;
+    table[0x3e] = &OpReturnDataCopy;
+    table[0x3f] = &OpExtCodeHash;
+
+    // Block Information
+    table[0x40] = &OpBlockHash;
+    table[0x41] = &OpCoinbase;
+    table[0x42] = &OpTimestamp;
+    table[0x43] = &OpNumber;
+    table[0x44] = &OpDifficulty;
+    table[0x45] = &OpGasLimit;
+    table[0x46] = &OpChainId;
+    table[0x48] = &OpBaseFee;
+
+    table[0x50] = &OpPop;
+    // Memory Operations
+    table[0x51] = &OpMemoryFn(OpMLoad);
+    table[0x52] = &OpMemoryFn(OpMStore);
+    table[0x53] = &OpMemoryFn(OpMStore8);
+
+    // Storage Operations
+    table[0x54] = &OpSLoad;
+    table[0x55] = &OpSStore;
+
+    // Control Flow Operations
+    table[0x56] = &OpJump;
+    table[0x57] = &OpJumpI;
+    table[0x58] = &OpPc;
+    table[0x5b] = &OpJumpDest;
+
+    // PUSHx
+    table[0x60] = &OpPushFn(OpPush1);
+    table[0x61] = &OpPushFn(OpPush2);
+    table[0x62] = &OpPushFn(OpPush3);
+    table[0x63] = &OpPushFn(OpPush4);
+    table[0x64] = &OpPushFn(OpPush5);
+    table[0x65] = &OpPushFn(OpPush6);
+    table[0x66] = &OpPushFn(OpPush7);
+    table[0x67] = &OpPushFn(OpPush8);
+    table[0x68] = &OpPushFn(OpPush9);
+    table[0x69] = &OpPushFn(OpPush10);
+    table[0x6a] = &OpPushFn(OpPush11);
+    table[0x6b] = &OpPushFn(OpPush12);
+    table[0x6c] = &OpPushFn(OpPush13);
+    table[0x6d] = &OpPushFn(OpPush14);
+    table[0x6e] = &OpPushFn(OpPush15);
+    table[0x6f] = &OpPushFn(OpPush16);
+    table[0x70] = &OpPushFn(OpPush17);
+    table[0x71] = &OpPushFn(OpPush18);
+    table[0x72] = &OpPushFn(OpPush19);
+    table[0x73] = &OpPushFn(OpPush20);
+    table[0x74] = &OpPushFn(OpPush21);
+    table[0x75] = &OpPushFn(OpPush22);
+    table[0x76] = &OpPushFn(OpPush23);
+    table[0x77] = &OpPushFn(OpPush24);
+    table[0x78] = &OpPushFn(OpPush25);
+    table[0x79] = &OpPushFn(OpPush26);
+    table[0x7a] = &OpPushFn(OpPush27);
+    table[0x7b] = &OpPushFn(OpPush28);
+    table[0x7c] = &OpPushFn(OpPush29);
+    table[0x7d] = &OpPushFn(OpPush30);
+    table[0x7e] = &OpPushFn(OpPush31);
+    table[0x7f] = &OpPushFn(OpPush32);
+
+    // DUPx
+    table[0x80] = &OpDupFn(OpDup1);
+    table[0x81] = &OpDupFn(OpDup2);
+    table[0x82] = &OpDupFn(OpDup3);
+    table[0x83] = &OpDupFn(OpDup4);
+    table[0x84] = &OpDupFn(OpDup5);
+    table[0x85] = &OpDupFn(OpDup6);
+    table[0x86] = &OpDupFn(OpDup7);
+    table[0x87] = &OpDupFn(OpDup8);
+    table[0x88] = &OpDupFn(OpDup9);
+    table[0x89] = &OpDupFn(OpDup10);
+    table[0x8a] = &OpDupFn(OpDup11);
+    table[0x8b] = &OpDupFn(OpDup12);
+    table[0x8c] = &OpDupFn(OpDup13);
+    table[0x8d] = &OpDupFn(OpDup14);
+    table[0x8e] = &OpDupFn(OpDup15);
+    table[0x8f] = &OpDupFn(OpDup16);
+
+    // SWAPx
+    table[0x90] = &OpSwapFn(OpSwap1);
+    table[0x91] = &OpSwapFn(OpSwap2);
+    table[0x92] = &OpSwapFn(OpSwap3);
+    table[0x93] = &OpSwapFn(OpSwap4);
+    table[0x94] = &OpSwapFn(OpSwap5);
+    table[0x95] = &OpSwapFn(OpSwap6);
+    table[0x96] = &OpSwapFn(OpSwap7);
+    table[0x97] = &OpSwapFn(OpSwap8);
+    table[0x98] = &OpSwapFn(OpSwap9);
+    table[0x99] = &OpSwapFn(OpSwap10);
+    table[0x9a] = &OpSwapFn(OpSwap11);
+    table[0x9b] = &OpSwapFn(OpSwap12);
+    table[0x9c] = &OpSwapFn(OpSwap13);
+    table[0x9d] = &OpSwapFn(OpSwap14);
+    table[0x9e] = &OpSwapFn(OpSwap15);
+    table[0x9f] = &OpSwapFn(OpSwap16);
+
+    // Logging Operations
+    table[0xa0] = &OpLog(0);
+    table[0xa1] = &OpLog(1);
+    table[0xa2] = &OpLog(2);
+    table[0xa3] = &OpLog(3);
+    table[0xa4] = &OpLog(4);
+
+    // Create Operations
+    table[0xf0] = &OpCreate;
+    table[0xf5] = &OpCreate2;
+
+    // Call Operations
+    table[0xf1] = &OpCall;
+    table[0xf2] = &OpCallCode;
+    table[0xf4] = &OpDelegateCall;
+    table[0xfa] = &OpStaticCall;
+
+    // Halting Operations
+    table[0xf3] = &OpReturn;
+    table[0xfd] = &OpRevert;
+    table[0xff] = &OpSelfDestruct;
+    JumpTable { ops: table, gas_table: None, static_costs: [None; 256] }
+};
+
+pub fn decode_op(opcode: u8, hardfork: Hardfork) -> &'static dyn OpcodeFn {
+    JUMP_TABLE.decode(opcode, hardfork)
+}
+
+/// the hardfork in which the opcode is introduced.
+pub fn opcode_hardfork(opcode: u8) -> Hardfork {
+    match opcode {
+        0xf4 => Hardfork::Homestead,
+        0x3d | 0x3e | 0xfa | 0xfd => Hardfork::Byzantium,
+        0x1b..=0x1d | 0x3f | 0xf5 => Hardfork::Constantinople,
+        0x46 | 0x47 => Hardfork::Istanbul,
+        0x48 => Hardfork::London,
+        0x5f => Hardfork::Shanghai,
+        0x49 | 0x4a | 0x5c..=0x5e => Hardfork::Cancun,
+        _ => Hardfork::Frontier,
+    }
+}
+
+/// mnemonic of the opcode. undefined opcodes are "INVALID".
+pub fn opcode_name(opcode: u8) -> &'static str {
+    match opcode {
+        0x00 => "STOP",
+        0x01 => "ADD",
+        0x02 => "MUL",
+        0x03 => "SUB",
+        0x04 => "DIV",
+        0x05 => "SDIV",
+        0x06 => "MOD",
+        0x07 => "SMOD",
+        0x08 => "ADDMOD",
+        0x09 => "MULMOD",
+        0x0a => "EXP",
+        0x0b => "SIGNEXTEND",
+        0x10 => "LT",
+        0x11 => "GT",
+        0x12 => "SLT",
+        0x13 => "SGT",
+        0x14 => "EQ",
+        0x15 => "ISZERO",
+        0x16 => "AND",
+        0x17 => "OR",
+        0x18 => "XOR",
+        0x19 => "NOT",
+        0x1a => "BYTE",
+        0x1b => "SHL",
+        0x1c => "SHR",
+        0x1d => "SAR",
+        0x20 => "SHA3",
+        0x30 => "ADDRESS",
+        0x31 => "BALANCE",
+        0x32 => "ORIGIN",
+        0x33 => "CALLER",
+        0x34 => "CALLVALUE",
+        0x35 => "CALLDATALOAD",
+        0x36 => "CALLDATASIZE",
+        0x37 => "CALLDATACOPY",
+        0x38 => "CODESIZE",
+        0x39 => "CODECOPY",
+        0x3a => "GASPRICE",
+        0x3b => "EXTCODESIZE",
+        0x3c => "EXTCODECOPY",
+        0x3d => "RETURNDATASIZE",
+        0x3e => "RETURNDATACOPY",
+        0x3f => "EXTCODEHASH",
+        0x40 => "BLOCKHASH",
+        0x41 => "COINBASE",
+        0x42 => "TIMESTAMP",
+        0x43 => "NUMBER",
+        0x44 => "DIFFICULTY",
+        0x45 => "GASLIMIT",
+        0x46 => "CHAINID",
+        0x47 => "SELFBALANCE",
+        0x48 => "BASEFEE",
+        0x49 => "BLOBHASH",
+        0x4a => "BLOBBASEFEE",
+        0x50 => "POP",
+        0x51 => "MLOAD",
+        0x52 => "MSTORE",
+        0x53 => "MSTORE8",
+        0x54 => "SLOAD",
+        0x55 => "SSTORE",
+        0x56 => "JUMP",
+        0x57 => "JUMPI",
+        0x58 => "PC",
+        0x59 => "MSIZE",
+        0x5a => "GAS",
+        0x5b => "JUMPDEST",
+        0x5c => "TLOAD",
+        0x5d => "TSTORE",
+        0x5e => "MCOPY",
+        0x5f => "PUSH0",
+        0x60 => "PUSH1",
+        0x61 => "PUSH2",
+        0x62 => "PUSH3",
+        0x63 => "PUSH4",
+        0x64 => "PUSH5",
+        0x65 => "PUSH6",
+        0x66 => "PUSH7",
+        0x67 => "PUSH8",
+        0x68 => "PUSH9",
+        0x69 => "PUSH10",
+        0x6a => "PUSH11",
+        0x6b => "PUSH12",
+        0x6c => "PUSH13",
+        0x6d => "PUSH14",
+        0x6e => "PUSH15",
+        0x6f => "PUSH16",
+        0x70 => "PUSH17",
+        0x71 => "PUSH18",
+        0x72 => "PUSH19",
+        0x73 => "PUSH20",
+        0x74 => "PUSH21",
+        0x75 => "PUSH22",
+        0x76 => "PUSH23",
+        0x77 => "PUSH24",
+        0x78 => "PUSH25",
+        0x79 => "PUSH26",
+        0x7a => "PUSH27",
+        0x7b => "PUSH28",
+        0x7c => "PUSH29",
+        0x7d => "PUSH30",
+        0x7e => "PUSH31",
+        0x7f => "PUSH32",
+        0x80 => "DUP1",
+        0x81 => "DUP2",
+        0x82 => "DUP3",
+        0x83 => "DUP4",
+        0x84 => "DUP5",
+        0x85 => "DUP6",
+        0x86 => "DUP7",
+        0x87 => "DUP8",
+        0x88 => "DUP9",
+        0x89 => "DUP10",
+        0x8a => "DUP11",
+        0x8b => "DUP12",
+        0x8c => "DUP13",
+        0x8d => "DUP14",
+        0x8e => "DUP15",
+        0x8f => "DUP16",
+        0x90 => "SWAP1",
+        0x91 => "SWAP2",
+        0x92 => "SWAP3",
+        0x93 => "SWAP4",
+        0x94 => "SWAP5",
+        0x95 => "SWAP6",
+        0x96 => "SWAP7",
+        0x97 => "SWAP8",
+        0x98 => "SWAP9",
+        0x99 => "SWAP10",
+        0x9a => "SWAP11",
+        0x9b => "SWAP12",
+        0x9c => "SWAP13",
+        0x9d => "SWAP14",
+        0x9e => "SWAP15",
+        0x9f => "SWAP16",
+        0xa0 => "LOG0",
+        0xa1 => "LOG1",
+        0xa2 => "LOG2",
+        0xa3 => "LOG3",
+        0xa4 => "LOG4",
+        0xf0 => "CREATE",
+        0xf1 => "CALL",
+        0xf2 => "CALLCODE",
+        0xf3 => "RETURN",
+        0xf4 => "DELEGATECALL",
+        0xf5 => "CREATE2",
+        0xfa => "STATICCALL",
+        0xfd => "REVERT",
+        0xfe => "INVALID",
+        0xff => "SELFDESTRUCT",
+        _ => "INVALID",
+    }
+}
+
+/// the opcode of the mnemonic. "INVALID" is the designated 0xfe.
+pub fn opcode_from_name(name: &str) -> Option<u8> {
+    (0..=255).rev().find(|opcode| opcode_name(*opcode) == name)
+}
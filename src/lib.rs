@@ -7,24 +7,42 @@ extern crate serde_json;
 #[macro_use]
 extern crate uint;
 
+use std::sync::Arc;
+
 use crate::core::*;
-use crate::instruction::decode_op;
+use crate::host::Host;
+use crate::instruction::{instruction_gas_cost, JumpTable, JUMP_TABLE};
+use crate::tracer::{NoopTracer, Tracer};
 
 pub mod instruction;
 pub mod core;
+pub mod host;
+pub mod tracer;
 #[macro_use]
 pub mod hex_util;
 
 serialize_as_hex_str!(Word Address);
 deserialize_from_hex!(Word Address);
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum ContextState {
     Processing,
     Success,
     Revert,
     Invalid,
     OutOfGas,
+    StackUnderflow,
+    StackOverflow,
+    /// tried to modify the state in a static frame.
+    StaticModeViolation,
+    /// the address of the new contract is already used.
+    CreateCollision,
+    /// the deployed code is larger than `MAX_CODE_SIZE` (EIP-170).
+    CodeSizeExceeded,
+    /// the deployed code starts with 0xEF (EIP-3541).
+    InvalidCode,
+    /// RETURNDATACOPY read out of the return data buffer.
+    ReturnDataOutOfBounds,
 }
 impl Default for ContextState {
     fn default() -> Self {
@@ -32,58 +50,536 @@ impl Default for ContextState {
     }
 }
 
+impl ContextState {
+    /// halted by an error which consumes all gas.
+    pub fn is_exceptional(&self) -> bool {
+        !matches!(self, ContextState::Processing | ContextState::Success | ContextState::Revert)
+    }
+}
+
+impl From<StackError> for ContextState {
+    fn from(e: StackError) -> Self {
+        match e {
+            StackError::Underflow => ContextState::StackUnderflow,
+            StackError::Overflow => ContextState::StackOverflow,
+        }
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct Context {
     state: ContextState,
     codes: Vec<u8>,
+    jumpdests: JumpDestMap,
     pc: usize,
-    stack: Vec<Word>,
+    stack: Stack,
     memory: Memory,
+    return_data: Vec<u8>,
+    /// output of the last sub-call, read by RETURNDATASIZE and RETURNDATACOPY.
+    return_data_buffer: Vec<u8>,
+    depth: u32,
+    address: Address,
+    caller: Address,
+    value: U256,
+    input_data: Vec<u8>,
+    hardfork: Hardfork,
+    /// the gas charged by the current instruction before its execution.
+    gas_cost: u64,
+    /// the custom gas schedule given by the jump table.
+    gas_table: Option<Arc<GasTable>>,
+    is_static: bool,
     remaining_gas: u64,
-    refund_gas: u64,
+    /// it can be negative in a frame, when a slot cleared by another frame is restored.
+    refund_gas: i64,
     used_gas: u64,
+    /// logs emitted in the frame and the succeeded sub-calls, in order.
+    logs: Vec<Log>,
+}
+
+/// the result of a message call.
+#[derive(Debug)]
+pub struct CallResult {
+    pub state: ContextState,
+    pub output: Vec<u8>,
+    pub gas_left: u64,
+    pub refund_gas: i64,
+    /// the address of the created contract. zero for the calls.
+    pub create_address: Address,
+    /// logs emitted in the call, in order. empty unless it is succeeded.
+    pub logs: Vec<Log>,
+}
+
+impl CallResult {
+    /// the call is failed before the execution, like the depth limit. the gas is not consumed.
+    fn failure(gas: u64) -> Self {
+        CallResult {
+            state: ContextState::Revert,
+            output: Vec::new(),
+            gas_left: gas,
+            refund_gas: 0,
+            create_address: Address::default(),
+            logs: Vec::new(),
+        }
+    }
 }
 
 pub trait ContextInterface {
     fn account_exists(&self, address: &Address) -> bool;
     fn set_storage(&mut self, address: &Address, key: &Word, value: &Word) -> StorageStatus;
     fn get_storage(&self, address: &Address, key: &Word) -> Word;
+    fn get_original_storage(&self, address: &Address, key: &Word) -> Word;
     fn get_balance(&self, address: &Address) -> U256;
     fn get_code_size(&self, address: &Address) -> usize;
     fn get_code_hash(&self, address: &Address) -> Word;
     fn copy_code(&self, address: &Address, buf: &mut [u8]) -> usize;
     fn selfdestruct(&mut self, address: &Address, beneficiary: &Address) -> bool;
-    fn call(message: &CallMessage) -> bool;
+    fn create_account(&mut self, address: &Address);
+    fn is_created(&self, address: &Address) -> bool;
+    fn transfer(&mut self, from: &Address, to: &Address, value: U256) -> bool;
+    fn get_nonce(&self, address: &Address) -> u64;
+    fn increment_nonce(&mut self, address: &Address);
+    fn set_code(&mut self, address: &Address, code: Vec<u8>);
+    fn call(&mut self, message: &CallMessage) -> CallResult;
     fn get_tx_context(&self) -> TransactionContext;
     fn get_block_hash(&self, number: u64) -> Word;
     fn emit_log(&mut self, address: &Address, data: &[u8], topics: &[Word]);
+    /// logs emitted in the transaction, in order.
+    fn logs(&self) -> &[Log];
+    /// the changes of the world state and the logs after the checkpoint can be undone by `revert_to`.
+    fn checkpoint(&mut self) -> Checkpoint;
+    fn revert_to(&mut self, checkpoint: Checkpoint);
+    fn access_account(&mut self, address: &Address) -> AccessStatus;
+    fn access_storage(&mut self, address: &Address, key: &Word) -> AccessStatus;
+    fn account_access_status(&self, address: &Address) -> AccessStatus;
+    fn storage_access_status(&self, address: &Address, key: &Word) -> AccessStatus;
 }
 
 impl Context {
+    pub fn new(codes: Vec<u8>, remaining_gas: u64) -> Self {
+        Context {
+            jumpdests: JumpDestMap::analyze(&codes),
+            codes,
+            remaining_gas,
+            .. Context::default()
+        }
+    }
+
+    /// context to run `codes` as the destination of the message.
+    pub fn with_message(codes: Vec<u8>, message: &CallMessage) -> Self {
+        Context {
+            depth: message.depth,
+            address: message.destination.clone(),
+            caller: message.sender.clone(),
+            value: message.value,
+            input_data: message.input_data.to_vec(),
+            is_static: message.is_static,
+            .. Context::new(codes, message.gas.max(0) as u64)
+        }
+    }
+
+    /// run with the rules of the given hardfork.
+    pub fn with_hardfork(mut self, hardfork: Hardfork) -> Self {
+        self.hardfork = hardfork;
+        self
+    }
+
     pub fn dump_stack(&self) {
         println!("stack: {:?}", self.stack)
     }
+
+    pub fn codes(&self) -> &[u8] {
+        &self.codes
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn stack(&self) -> &Stack {
+        &self.stack
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    pub fn state(&self) -> &ContextState {
+        &self.state
+    }
+
+    /// output data set by RETURN or REVERT.
+    pub fn return_data(&self) -> &[u8] {
+        &self.return_data
+    }
+
+    /// output of the last sub-call.
+    pub fn return_data_buffer(&self) -> &[u8] {
+        &self.return_data_buffer
+    }
+
+    /// logs emitted in the frame and the succeeded sub-calls, in order.
+    pub fn logs(&self) -> &[Log] {
+        &self.logs
+    }
+
+    /// the gas charged by the current instruction before its execution, including the state-dependent cost.
+    pub fn gas_cost(&self) -> u64 {
+        self.gas_cost
+    }
+
+    pub fn used_gas(&self) -> u64 {
+        self.used_gas
+    }
+
+    pub fn gas_left(&self) -> u64 {
+        self.remaining_gas - self.used_gas
+    }
+
+    /// gas to be refunded. it is capped by the hardfork rule when `execute` or `execute_message` finishes the top level frame.
+    pub fn refund_gas(&self) -> i64 {
+        self.refund_gas
+    }
+
+    /// call depth. the top level frame is 0.
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// address of the account whose code is executing.
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    pub fn caller(&self) -> &Address {
+        &self.caller
+    }
+
+    pub fn value(&self) -> U256 {
+        self.value
+    }
+
+    /// call data of the message.
+    pub fn input_data(&self) -> &[u8] {
+        &self.input_data
+    }
+
+    /// the gas schedule of the execution. it is `DEFAULT_GAS_TABLE` unless the jump table has the custom one.
+    pub fn gas_table(&self) -> &GasTable {
+        self.gas_table.as_deref().unwrap_or(&DEFAULT_GAS_TABLE)
+    }
+
+    pub fn hardfork(&self) -> Hardfork {
+        self.hardfork
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.is_static
+    }
 }
 
+/// execute the code with an empty world state.
 pub fn execute(opecodes: Vec<u8>, remaining_gas: u64) -> Context {
-    let max_pc = opecodes.len();
-    println!("{:?}", opecodes);
-    let mut ctx = Context {
-        codes: opecodes,
-        remaining_gas,
-        .. Context::default()
+    execute_with_tracer(opecodes, remaining_gas, &mut NoopTracer)
+}
+
+pub fn execute_with_tracer<T: Tracer + ?Sized>(opecodes: Vec<u8>, remaining_gas: u64, tracer: &mut T) -> Context {
+    let mut host = Host::new(OnMemoryWorldState::default(), TransactionContext::default());
+    finish_top_level(interpret(Context::new(opecodes, remaining_gas), &JUMP_TABLE, &mut host, tracer))
+}
+
+pub fn execute_message(codes: Vec<u8>, message: &CallMessage, host: &mut dyn ContextInterface) -> Context {
+    execute_message_with_tracer(codes, message, host, &mut NoopTracer)
+}
+
+pub fn execute_message_with_tracer<T: Tracer + ?Sized>(codes: Vec<u8>, message: &CallMessage, host: &mut dyn ContextInterface, tracer: &mut T) -> Context {
+    finish_top_level(interpret(Context::with_message(codes, message), &JUMP_TABLE, host, tracer))
+}
+
+/// the refund is capped by the gas used in the transaction (EIP-3529).
+pub(crate) fn cap_refund(refund: i64, used_gas: u64, hardfork: Hardfork) -> i64 {
+    refund.clamp(0, (used_gas / hardfork.max_refund_quotient()) as i64)
+}
+
+/// the top level frame run without the transaction is the whole transaction.
+/// the refund is capped, and nothing is refunded if it is not succeeded.
+fn finish_top_level(mut ctx: Context) -> Context {
+    if ctx.depth == 0 {
+        ctx.refund_gas = if ctx.state == ContextState::Success {
+            cap_refund(ctx.refund_gas, ctx.used_gas, ctx.hardfork)
+        } else {
+            0
+        };
+    }
+    ctx
+}
+
+/// the maximum depth of the nested calls.
+pub const CALL_DEPTH_LIMIT: u32 = 1024;
+
+/// execute the message with the code of `message.code_address`.
+/// the value is transferred before the execution. all the changes in the call are reverted if it is not succeeded.
+pub fn execute_call<T: Tracer + ?Sized>(host: &mut dyn ContextInterface, message: &CallMessage, hardfork: Hardfork, table: &JumpTable, tracer: &mut T) -> CallResult {
+    let gas = message.gas.max(0) as u64;
+    if message.depth > CALL_DEPTH_LIMIT {
+        return CallResult::failure(gas);
+    }
+    if let CallKind::Create | CallKind::Create2 = message.kind {
+        return execute_create(host, message, hardfork, table, tracer);
+    }
+    let transfer = match message.kind {
+        CallKind::Call | CallKind::CallCode => !message.value.is_zero(),
+        _ => false,
     };
+    let checkpoint = host.checkpoint();
+    if transfer && !host.transfer(&message.sender, &message.destination, message.value) {
+        return CallResult::failure(gas);
+    }
+
+    let mut codes = vec![0; host.get_code_size(&message.code_address)];
+    host.copy_code(&message.code_address, &mut codes);
+    let ctx = interpret(Context::with_message(codes, message).with_hardfork(hardfork), table, host, tracer);
+
+    if ctx.state != ContextState::Success {
+        host.revert_to(checkpoint);
+    }
+    CallResult {
+        gas_left: if ctx.state.is_exceptional() { 0 } else { ctx.gas_left() },
+        refund_gas: if ctx.state == ContextState::Success { ctx.refund_gas } else { 0 },
+        logs: if ctx.state == ContextState::Success { ctx.logs } else { Vec::new() },
+        state: ctx.state,
+        output: ctx.return_data,
+        create_address: Address::default(),
+    }
+}
+
+/// the maximum size of the deployed code (EIP-170).
+pub const MAX_CODE_SIZE: usize = 24576;
+
+/// run `message.input_data` as the init code and install the output as the code of the new account.
+/// the nonce of the sender is incremented even if the creation is not succeeded, but the other changes are reverted.
+fn execute_create<T: Tracer + ?Sized>(host: &mut dyn ContextInterface, message: &CallMessage, hardfork: Hardfork, table: &JumpTable, tracer: &mut T) -> CallResult {
+    let gas = message.gas.max(0) as u64;
+    let nonce = host.get_nonce(&message.sender);
+    if host.get_balance(&message.sender) < message.value || nonce == u64::MAX {
+        return CallResult::failure(gas);
+    }
+    let address = match message.kind {
+        CallKind::Create2 => Address::create2(&message.sender, &message.create2_salt, message.input_data),
+        _ => Address::create(&message.sender, nonce),
+    };
+    host.increment_nonce(&message.sender);
+    host.access_account(&address);
+    if host.get_nonce(&address) != 0 || host.get_code_size(&address) != 0 {
+        return CallResult {
+            state: ContextState::CreateCollision,
+            output: Vec::new(),
+            gas_left: 0,
+            refund_gas: 0,
+            create_address: Address::default(),
+            logs: Vec::new(),
+        };
+    }
+    let checkpoint = host.checkpoint();
+    host.create_account(&address);
+    if hardfork >= Hardfork::SpuriousDragon {
+        // the nonce of the new contract starts from 1 (EIP-161).
+        host.increment_nonce(&address);
+    }
+    host.transfer(&message.sender, &address, message.value);
+
+    let init_message = CallMessage {
+        destination: address.clone(),
+        code_address: address.clone(),
+        input_data: &[],
+        .. message.clone()
+    };
+    let mut ctx = interpret(Context::with_message(message.input_data.to_vec(), &init_message).with_hardfork(hardfork), table, host, tracer);
+
+    if ctx.state == ContextState::Success {
+        let code_size = ctx.return_data.len();
+        if hardfork >= Hardfork::SpuriousDragon && code_size > MAX_CODE_SIZE {
+            ctx.state = ContextState::CodeSizeExceeded;
+        } else if hardfork >= Hardfork::London && ctx.return_data.first() == Some(&0xef) {
+            ctx.state = ContextState::InvalidCode;
+        } else if ctx.gas_table().create_data_cost * code_size as u64 > ctx.gas_left() {
+            ctx.state = ContextState::OutOfGas;
+        } else {
+            ctx.used_gas += ctx.gas_table().create_data_cost * code_size as u64;
+            host.set_code(&address, std::mem::take(&mut ctx.return_data));
+        }
+    }
+    if ctx.state != ContextState::Success {
+        host.revert_to(checkpoint);
+    }
+    if ctx.state.is_exceptional() {
+        ctx.return_data.clear();
+    }
+    CallResult {
+        gas_left: if ctx.state.is_exceptional() { 0 } else { ctx.gas_left() },
+        refund_gas: if ctx.state == ContextState::Success { ctx.refund_gas } else { 0 },
+        create_address: if ctx.state == ContextState::Success { address } else { Address::default() },
+        logs: if ctx.state == ContextState::Success { ctx.logs } else { Vec::new() },
+        state: ctx.state,
+        output: ctx.return_data,
+    }
+}
+
+/// the host seen from the instructions. the nested calls run with the same jump table and tracer.
+struct Frame<'a, T: Tracer + ?Sized> {
+    host: &'a mut dyn ContextInterface,
+    table: &'a JumpTable,
+    tracer: &'a mut T,
+    hardfork: Hardfork,
+}
+
+impl<'a, T: Tracer + ?Sized> ContextInterface for Frame<'a, T> {
+    fn account_exists(&self, address: &Address) -> bool {
+        self.host.account_exists(address)
+    }
+
+    fn set_storage(&mut self, address: &Address, key: &Word, value: &Word) -> StorageStatus {
+        self.host.set_storage(address, key, value)
+    }
+
+    fn get_storage(&self, address: &Address, key: &Word) -> Word {
+        self.host.get_storage(address, key)
+    }
+
+    fn get_original_storage(&self, address: &Address, key: &Word) -> Word {
+        self.host.get_original_storage(address, key)
+    }
+
+    fn get_balance(&self, address: &Address) -> U256 {
+        self.host.get_balance(address)
+    }
+
+    fn get_code_size(&self, address: &Address) -> usize {
+        self.host.get_code_size(address)
+    }
+
+    fn get_code_hash(&self, address: &Address) -> Word {
+        self.host.get_code_hash(address)
+    }
+
+    fn copy_code(&self, address: &Address, buf: &mut [u8]) -> usize {
+        self.host.copy_code(address, buf)
+    }
+
+    fn selfdestruct(&mut self, address: &Address, beneficiary: &Address) -> bool {
+        self.host.selfdestruct(address, beneficiary)
+    }
+
+    fn create_account(&mut self, address: &Address) {
+        self.host.create_account(address)
+    }
+
+    fn is_created(&self, address: &Address) -> bool {
+        self.host.is_created(address)
+    }
+
+    fn transfer(&mut self, from: &Address, to: &Address, value: U256) -> bool {
+        self.host.transfer(from, to, value)
+    }
+
+    fn get_nonce(&self, address: &Address) -> u64 {
+        self.host.get_nonce(address)
+    }
+
+    fn increment_nonce(&mut self, address: &Address) {
+        self.host.increment_nonce(address)
+    }
+
+    fn set_code(&mut self, address: &Address, code: Vec<u8>) {
+        self.host.set_code(address, code)
+    }
+
+    fn call(&mut self, message: &CallMessage) -> CallResult {
+        execute_call(&mut *self.host, message, self.hardfork, self.table, &mut *self.tracer)
+    }
+
+    fn get_tx_context(&self) -> TransactionContext {
+        self.host.get_tx_context()
+    }
+
+    fn get_block_hash(&self, number: u64) -> Word {
+        self.host.get_block_hash(number)
+    }
+
+    fn emit_log(&mut self, address: &Address, data: &[u8], topics: &[Word]) {
+        self.host.emit_log(address, data, topics)
+    }
+
+    fn logs(&self) -> &[Log] {
+        self.host.logs()
+    }
+
+    fn checkpoint(&mut self) -> Checkpoint {
+        self.host.checkpoint()
+    }
 
-    while ctx.pc < max_pc {
-        ctx = decode_op(ctx.codes[ctx.pc]).instruct(ctx);
-        ctx.dump_stack();
+    fn revert_to(&mut self, checkpoint: Checkpoint) {
+        self.host.revert_to(checkpoint)
     }
+
+    fn access_account(&mut self, address: &Address) -> AccessStatus {
+        self.host.access_account(address)
+    }
+
+    fn access_storage(&mut self, address: &Address, key: &Word) -> AccessStatus {
+        self.host.access_storage(address, key)
+    }
+
+    fn account_access_status(&self, address: &Address) -> AccessStatus {
+        self.host.account_access_status(address)
+    }
+
+    fn storage_access_status(&self, address: &Address, key: &Word) -> AccessStatus {
+        self.host.storage_access_status(address, key)
+    }
+}
+
+/// run the context until it halts, with the instructions of the given jump table.
+pub fn interpret<T: Tracer + ?Sized>(mut ctx: Context, table: &JumpTable, host: &mut dyn ContextInterface, tracer: &mut T) -> Context {
+    ctx.gas_table = table.gas_table().cloned();
+    // the logs of the frame are the ones emitted by the host after this, including the succeeded sub-calls.
+    let logs_start = host.logs().len();
+    let mut frame = Frame { host, table, tracer, hardfork: ctx.hardfork };
+    frame.tracer.start(&ctx);
+    while ctx.pc < ctx.codes.len() {
+        let op = table.decode(ctx.codes[ctx.pc], ctx.hardfork);
+        let cost = instruction_gas_cost(op, table, &ctx, &frame);
+        ctx.gas_cost = cost.unwrap_or(0);
+        frame.tracer.step_start(&ctx, op);
+        op.instruct(&mut ctx, &mut frame, cost);
+        if !ctx.state.is_exceptional() && op.stack_io() != (0, 0) {
+            frame.tracer.stack_changed(&ctx.stack);
+        }
+        if let Some((offset, size)) = ctx.memory.take_last_write() {
+            let start = offset as usize;
+            frame.tracer.memory_changed(offset, &ctx.memory.as_ref()[start..start + size]);
+        }
+        frame.tracer.step_end(&ctx);
+    }
+    if ctx.state == ContextState::Processing {
+        // running off the end of the code is the same as STOP.
+        ctx.state = ContextState::Success;
+    }
+    if ctx.state == ContextState::Success {
+        ctx.logs = frame.host.logs()[logs_start..].to_vec();
+    }
+    frame.tracer.halt(&ctx);
     ctx
 }
 
-pub mod tests {
-    use crate::core::U256;
-    use crate::execute;
+#[cfg(test)]
+mod tests {
+    use crate::core::{AccessList, AccessListItem, Account, Address, CallKind, CallMessage, GasTable, OnMemoryWorldState, TransactionContext, Word, U256};
+    use crate::host::Host;
+    use crate::{execute, execute_call, execute_message, interpret, Context, ContextInterface, ContextState};
+    use crate::core::Hardfork;
+    use crate::instruction::{JumpTable, JUMP_TABLE};
+    use crate::tracer::NoopTracer;
+    use crate::hex_util::FromHex;
     use crate::hex_util::ToHex;
 
     const U256_MAX_BYTES: [u8; 32] = [
@@ -93,6 +589,22 @@ pub mod tests {
         0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
     ];
 
+    /// a top level CALL from the zero address without the value.
+    fn call_message<'a>(to: &Address, input: &'a [u8], gas: i64) -> CallMessage<'a> {
+        CallMessage {
+            kind: CallKind::Call,
+            depth: 0,
+            gas,
+            destination: to.clone(),
+            code_address: to.clone(),
+            sender: Address::default(),
+            input_data: input,
+            value: U256::zero(),
+            create2_salt: Word::ZERO,
+            is_static: false,
+        }
+    }
+
     #[test]
     fn test_overflow_add() {
         let mut ctx = execute(vec![
@@ -659,7 +1171,7 @@ pub mod tests {
             0x60, 0x80,
             0x60, 0x40,
             0x52], 100000);
-        assert!(ctx.stack.pop().is_none());
+        assert!(ctx.stack.pop().is_err());
         let expect: &[u8] = &[
             0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
             0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
@@ -716,7 +1228,7 @@ pub mod tests {
             0x60, 0x40,
             0x53,
         ], 100000);
-        assert!(ctx.stack.pop().is_none());
+        assert!(ctx.stack.pop().is_err());
         let expect: &[u8] = &[
             0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
             0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
@@ -748,4 +1260,1377 @@ pub mod tests {
         assert_eq!(ctx.stack.pop().unwrap().to_hex(), "9c22ff5f21f0b81b113e63f7db6da94fedef11b2119b4088b89664fb9a3cb658");
         assert_eq!(ctx.used_gas, 60);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_pop() {
+        let mut ctx = execute(vec![
+            0x60, 0x01,
+            0x60, 0x02,
+            0x50], 100000);
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(1));
+        assert!(ctx.stack.pop().is_err());
+        assert_eq!(ctx.used_gas, 8);
+
+        // pop from empty stack
+        let ctx = execute(vec![0x50], 100000);
+        assert_eq!(ctx.state, ContextState::StackUnderflow);
+    }
+
+    #[test]
+    fn test_dup() {
+        // DUP1
+        let mut ctx = execute(vec![
+            0x60, 0x01,
+            0x80], 100000);
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(1));
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(1));
+        assert_eq!(ctx.used_gas, 6);
+
+        // DUP3
+        let mut ctx = execute(vec![
+            0x60, 0x03,
+            0x60, 0x02,
+            0x60, 0x01,
+            0x82], 100000);
+        assert_eq!(ctx.stack.len(), 4);
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(3));
+
+        // DUP16 needs 16 items
+        let mut codes = Vec::new();
+        for i in 0..16 {
+            codes.extend_from_slice(&[0x60, i]);
+        }
+        codes.push(0x8f);
+        let mut ctx = execute(codes, 100000);
+        assert_eq!(ctx.stack.len(), 17);
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(0));
+
+        // stack underflow
+        let ctx = execute(vec![
+            0x60, 0x01,
+            0x81], 100000);
+        assert_eq!(ctx.state, ContextState::StackUnderflow);
+    }
+
+    #[test]
+    fn test_swap() {
+        // SWAP1
+        let mut ctx = execute(vec![
+            0x60, 0x01,
+            0x60, 0x02,
+            0x90], 100000);
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(1));
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(2));
+        assert_eq!(ctx.used_gas, 9);
+
+        // SWAP2
+        let mut ctx = execute(vec![
+            0x60, 0x03,
+            0x60, 0x02,
+            0x60, 0x01,
+            0x91], 100000);
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(3));
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(2));
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(1));
+
+        // SWAP16 needs 17 items
+        let mut codes = Vec::new();
+        for i in 0..17 {
+            codes.extend_from_slice(&[0x60, i]);
+        }
+        codes.push(0x9f);
+        let mut ctx = execute(codes, 100000);
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(0));
+        assert_eq!(U256::from(ctx.stack.as_ref()[0]), U256::from(16));
+
+        // stack underflow
+        let ctx = execute(vec![
+            0x60, 0x01,
+            0x90], 100000);
+        assert_eq!(ctx.state, ContextState::StackUnderflow);
+    }
+
+    #[test]
+    fn test_jump() {
+        // jump over the INVALID
+        let mut ctx = execute(vec![
+            0x60, 0x04,
+            0x56,
+            0xfe,
+            0x5b,
+            0x60, 0x01], 100000);
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(1));
+        assert_eq!(ctx.used_gas, 3 + 8 + 1 + 3);
+
+        // destination is not a JUMPDEST
+        let ctx = execute(vec![
+            0x60, 0x03,
+            0x56,
+            0x60, 0x5b], 100000);
+        assert_eq!(ctx.state, ContextState::Invalid);
+
+        // destination is a JUMPDEST byte inside PUSH data
+        let ctx = execute(vec![
+            0x60, 0x04,
+            0x56,
+            0x60, 0x5b], 100000);
+        assert_eq!(ctx.state, ContextState::Invalid);
+
+        // destination is out of code
+        let ctx = execute(vec![
+            0x60, 0xff,
+            0x56], 100000);
+        assert_eq!(ctx.state, ContextState::Invalid);
+    }
+
+    #[test]
+    fn test_jumpi() {
+        // count down from 5 to 0
+        let mut ctx = execute(vec![
+            0x60, 0x05,
+            0x5b,
+            0x60, 0x01,
+            0x90,
+            0x03,
+            0x80,
+            0x60, 0x02,
+            0x57], 100000);
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(0));
+        assert!(ctx.stack.pop().is_err());
+
+        // condition is zero, the destination is not checked.
+        let mut ctx = execute(vec![
+            0x60, 0x00,
+            0x60, 0xff,
+            0x57,
+            0x60, 0x01], 100000);
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(1));
+    }
+
+    #[test]
+    fn test_pc() {
+        let mut ctx = execute(vec![
+            0x60, 0x00,
+            0x50,
+            0x58], 100000);
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(3));
+    }
+
+    #[test]
+    fn test_stop() {
+        let mut ctx = execute(vec![
+            0x60, 0x01,
+            0x00,
+            0x60, 0x02], 100000);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(1));
+        assert!(ctx.stack.pop().is_err());
+        assert_eq!(ctx.used_gas, 3);
+
+        // running off the end of the code
+        let ctx = execute(vec![0x60, 0x01], 100000);
+        assert_eq!(ctx.state, ContextState::Success);
+    }
+
+    #[test]
+    fn test_return() {
+        let ctx = execute(vec![
+            0x61, 0x12, 0x34,
+            0x60, 0x00,
+            0x52,
+            0x60, 0x02,
+            0x60, 0x1e,
+            0xf3,
+            0x60, 0x01], 100000);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.return_data(), &[0x12, 0x34]);
+        assert_eq!(ctx.used_gas, 18);
+        assert!(ctx.stack.is_empty());
+
+        // return nothing
+        let ctx = execute(vec![
+            0x60, 0x00,
+            0x60, 0xff,
+            0xf3], 100000);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert!(ctx.return_data().is_empty());
+        assert_eq!(ctx.memory.len(), 0);
+    }
+
+    #[test]
+    fn test_revert() {
+        let ctx = execute(vec![
+            0x60, 0xab,
+            0x60, 0x00,
+            0x53,
+            0x60, 0x01,
+            0x60, 0x00,
+            0xfd], 100000);
+        assert_eq!(ctx.state, ContextState::Revert);
+        assert_eq!(ctx.return_data(), &[0xab]);
+        // the remaining gas is not consumed.
+        assert_eq!(ctx.used_gas, 18);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let ctx = execute(vec![
+            0x60, 0x01,
+            0xfe,
+            0x60, 0x02], 100000);
+        assert_eq!(ctx.state, ContextState::Invalid);
+        // exceptional halt consumes all gas.
+        assert_eq!(ctx.used_gas, 100000);
+        assert_eq!(ctx.stack.len(), 1);
+    }
+
+    #[test]
+    fn test_out_of_gas() {
+        // PUSH1 x2 = 6 gas, ADD needs 3 more.
+        let ctx = execute(vec![
+            0x60, 0x01,
+            0x60, 0x02,
+            0x01], 8);
+        assert_eq!(ctx.state, ContextState::OutOfGas);
+        assert_eq!(ctx.used_gas, 8);
+        // ADD is not executed.
+        assert_eq!(ctx.stack.len(), 2);
+
+        // MSTORE needs 3 + 3 for memory expansion.
+        let ctx = execute(vec![
+            0x60, 0x80,
+            0x60, 0x00,
+            0x52], 11);
+        assert_eq!(ctx.state, ContextState::OutOfGas);
+        assert_eq!(ctx.memory.len(), 0);
+
+        let ctx = execute(vec![
+            0x60, 0x80,
+            0x60, 0x00,
+            0x52], 12);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.used_gas, 12);
+
+        // EXP 2^256 needs 10 + 50 * 2
+        let ctx = execute(vec![
+            0x61, 0x01, 0x00,
+            0x60, 0x02,
+            0x0a], 115);
+        assert_eq!(ctx.state, ContextState::OutOfGas);
+        assert_eq!(ctx.stack.len(), 2);
+
+        // SHA3 over a huge range can never be paid.
+        let ctx = execute(vec![
+            0x64, 0x01, 0x00, 0x00, 0x00, 0x00,
+            0x60, 0x00,
+            0x20], 100000);
+        assert_eq!(ctx.state, ContextState::OutOfGas);
+        assert_eq!(ctx.memory.len(), 0);
+    }
+
+    #[test]
+    fn test_stack_underflow() {
+        let ctx = execute(vec![
+            0x60, 0x01,
+            0x01], 100000);
+        assert_eq!(ctx.state, ContextState::StackUnderflow);
+        assert_eq!(ctx.used_gas, 100000);
+        assert_eq!(ctx.stack.len(), 1);
+
+        let ctx = execute(vec![0x52], 100000);
+        assert_eq!(ctx.state, ContextState::StackUnderflow);
+    }
+
+    #[test]
+    fn test_stack_overflow() {
+        let mut codes = vec![0x60, 0x01];
+        codes.extend_from_slice(&[0x80; 1023]);
+        let ctx = execute(codes.clone(), 100000);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.stack.len(), 1024);
+
+        codes.push(0x80);
+        let ctx = execute(codes, 100000);
+        assert_eq!(ctx.state, ContextState::StackOverflow);
+        assert_eq!(ctx.stack.len(), 1024);
+    }
+
+    #[test]
+    fn test_storage_of_missing_account() {
+        // SLOAD(0) of the account which does not exist.
+        let mut ctx = execute(vec![0x60, 0x00, 0x54], 100000);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.stack.pop().unwrap(), Word::ZERO);
+        // SSTORE(0, 1)
+        let ctx = execute(vec![0x60, 0x01, 0x60, 0x00, 0x55], 100000);
+        assert_eq!(ctx.state, ContextState::Success);
+    }
+
+    #[test]
+    fn test_sstore_out_of_gas() {
+        let message = CallMessage {
+            sender: Address::from_hex("00000000000000000000000000000000000000bb").unwrap(),
+            .. call_message(&Address::from_hex("00000000000000000000000000000000000000aa").unwrap(), &[], 5000)
+        };
+        // SSTORE(0, 1) can not afford the 20000 gas, so the slot must not be written.
+        let mut host = Host::new(OnMemoryWorldState::default(), TransactionContext::default());
+        let ctx = execute_message(vec![0x60, 0x01, 0x60, 0x00, 0x55], &message, &mut host);
+        assert_eq!(ctx.state, ContextState::OutOfGas);
+        assert_eq!(host.get_storage(&message.destination, &Word::ZERO), Word::ZERO);
+    }
+
+    #[test]
+    fn test_environment() {
+        let message = CallMessage {
+            sender: Address::from_hex("00000000000000000000000000000000000000bb").unwrap(),
+            value: U256::from(1000),
+            .. call_message(&Address::from_hex("00000000000000000000000000000000000000aa").unwrap(), &[], 100000)
+        };
+        let tx_context = TransactionContext {
+            gas_price: U256::from(20),
+            origin: Address::from_hex("00000000000000000000000000000000000000cc").unwrap(),
+            .. TransactionContext::default()
+        };
+        // ADDRESS CALLER CALLVALUE ORIGIN GASPRICE
+        let mut host = Host::new(OnMemoryWorldState::default(), tx_context);
+        let mut ctx = execute_message(vec![0x30, 0x33, 0x34, 0x32, 0x3a], &message, &mut host);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.used_gas, 10);
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(20));
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(0xcc));
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(1000));
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(0xbb));
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(0xaa));
+    }
+
+    #[test]
+    fn test_calldata() {
+        let input_data: Vec<u8> = (1..=36).collect();
+        let message = call_message(&Address::default(), &input_data, 100000);
+        let mut host = Host::new(OnMemoryWorldState::default(), TransactionContext::default());
+        // CALLDATASIZE, CALLDATALOAD(4), CALLDATALOAD(20) reads over the end
+        let mut ctx = execute_message(vec![
+            0x36,
+            0x60, 0x04, 0x35,
+            0x60, 0x14, 0x35], &message, &mut host);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.used_gas, 14);
+        assert_eq!(ctx.stack.pop().unwrap().to_hex(), "15161718191a1b1c1d1e1f202122232400000000000000000000000000000000");
+        assert_eq!(ctx.stack.pop().unwrap().to_hex(), "05060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f2021222324");
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(36));
+
+        // CALLDATACOPY(0, 32, 8)
+        let ctx = execute_message(vec![
+            0x60, 0x08,
+            0x60, 0x20,
+            0x60, 0x00,
+            0x37], &message, &mut host);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.used_gas, 3 * 3 + 3 + 3 + 3);
+        assert_eq!(&ctx.memory.as_ref()[..8], &[33, 34, 35, 36, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_code() {
+        // CODESIZE, CODECOPY(0, 2, 4)
+        let ctx = execute(vec![
+            0x38,
+            0x60, 0x04,
+            0x60, 0x02,
+            0x60, 0x00,
+            0x39], 100000);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.used_gas, 2 + 3 * 3 + 3 + 3 + 3);
+        assert_eq!(U256::from(ctx.stack.peek(0).unwrap()), U256::from(8));
+        assert_eq!(&ctx.memory.as_ref()[..4], &[0x04, 0x60, 0x02, 0x60]);
+    }
+
+    #[test]
+    fn test_ext_code() {
+        let address = Address::from_hex("00000000000000000000000000000000000000aa").unwrap();
+        let mut world_state = OnMemoryWorldState::default();
+        world_state.insert(address.clone(), Account {
+            address: address.clone(),
+            code: vec![0x60, 0x01, 0x60, 0x02, 0x01],
+            .. Account::default()
+        });
+        let mut host = Host::new(world_state, TransactionContext::default());
+        let message = call_message(&Address::default(), &[], 100000);
+        // EXTCODESIZE(0xaa), EXTCODEHASH(0xaa), EXTCODEHASH(0xbb), EXTCODECOPY(0xaa, 0, 3, 4)
+        let mut ctx = execute_message(vec![
+            0x60, 0xaa, 0x3b,
+            0x60, 0xaa, 0x3f,
+            0x60, 0xbb, 0x3f,
+            0x60, 0x04,
+            0x60, 0x03,
+            0x60, 0x00,
+            0x60, 0xaa,
+            0x3c], &message, &mut host);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.used_gas, 3 * 3 + 700 * 3 + 3 * 4 + 700 + 3 + 3);
+        assert_eq!(ctx.stack.pop().unwrap(), Word::ZERO);
+        assert_eq!(ctx.stack.pop().unwrap().to_hex(), "decae447f2d47ba6808e14c600b3b7611ce1cc9a632546acfd1130ca42c07c4d");
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(5));
+        assert_eq!(&ctx.memory.as_ref()[..4], &[0x02, 0x01, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_block_info() {
+        let tx_context = TransactionContext {
+            block_coinbase: Address::from_hex("00000000000000000000000000000000000000cc").unwrap(),
+            block_number: 1000,
+            block_timestamp: 1600000000,
+            block_gas_limit: 30000000,
+            block_difficulty: U256::from(0x5678),
+            block_base_fee: U256::from(7),
+            block_prev_randao: U256::from(0x1234),
+            chain_id: U256::from(1),
+            .. TransactionContext::default()
+        };
+        let mut host = Host::new(OnMemoryWorldState::default(), tx_context);
+        let message = call_message(&Address::default(), &[], 100000);
+        // COINBASE TIMESTAMP NUMBER PREVRANDAO GASLIMIT CHAINID BASEFEE
+        let mut ctx = interpret(
+            Context::with_message(vec![0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x48], &message).with_hardfork(Hardfork::Paris),
+            &JUMP_TABLE, &mut host, &mut NoopTracer);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.used_gas, 14);
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(7));
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(1));
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(30000000));
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(0x1234));
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(1000));
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(1600000000));
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(0xcc));
+
+        // DIFFICULTY before the merge.
+        let mut ctx = interpret(
+            Context::with_message(vec![0x44], &message).with_hardfork(Hardfork::London),
+            &JUMP_TABLE, &mut host, &mut NoopTracer);
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(0x5678));
+
+        let hash = Word::from_hex("00000000000000000000000000000000000000000000000000000000000000aa").unwrap();
+        host.set_block_hash(744, hash);
+        host.set_block_hash(743, hash);
+        host.set_block_hash(999, hash);
+        // BLOCKHASH(999), BLOCKHASH(744), BLOCKHASH(743), BLOCKHASH(1000)
+        let mut ctx = execute_message(vec![
+            0x61, 0x03, 0xe7, 0x40,
+            0x61, 0x02, 0xe8, 0x40,
+            0x61, 0x02, 0xe7, 0x40,
+            0x61, 0x03, 0xe8, 0x40], &message, &mut host);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.used_gas, (3 + 20) * 4);
+        assert_eq!(ctx.stack.pop().unwrap(), Word::ZERO);
+        assert_eq!(ctx.stack.pop().unwrap(), Word::ZERO);
+        assert_eq!(ctx.stack.pop().unwrap(), hash);
+        assert_eq!(ctx.stack.pop().unwrap(), hash);
+    }
+
+    #[test]
+    fn test_storage() {
+        let mut world_state = OnMemoryWorldState::default();
+        let mut account = Account::default();
+        account.storage.0.insert(Word::ZERO, Word::from(U256::from(1)));
+        world_state.insert(Address::default(), account);
+        let message = call_message(&Address::default(), &[], 100000);
+        // SSTORE(1, 1), SLOAD(1), SSTORE(0, 0)
+        let codes = vec![
+            0x60, 0x01, 0x60, 0x01, 0x55,
+            0x60, 0x01, 0x54,
+            0x60, 0x00, 0x60, 0x00, 0x55];
+        let mut host = Host::new(world_state.clone(), TransactionContext::default());
+        let mut ctx = execute_message(codes.clone(), &message, &mut host);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.used_gas, 3 + 3 + 20000 + 3 + 800 + 3 + 3 + 5000);
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(1));
+        // 15000 is capped by the half of the used gas.
+        assert_eq!(ctx.refund_gas, 25815 / 2);
+
+        let mut host = Host::new(world_state.clone(), TransactionContext::default());
+        let ctx = interpret(
+            Context::with_message(codes, &message).with_hardfork(Hardfork::London),
+            &JUMP_TABLE, &mut host, &mut NoopTracer);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.refund_gas, 4800);
+    }
+
+    #[test]
+    fn test_sstore_sentry() {
+        let mut world_state = OnMemoryWorldState::default();
+        world_state.insert(Address::default(), Account::default());
+        let message = call_message(&Address::default(), &[], 3 + 3 + 2300);
+        let mut host = Host::new(world_state, TransactionContext::default());
+        let ctx = execute_message(vec![0x60, 0x01, 0x60, 0x00, 0x55], &message, &mut host);
+        assert_eq!(ctx.state, ContextState::OutOfGas);
+        assert_eq!(ctx.refund_gas, 0);
+        assert_eq!(host.get_storage(&Address::default(), &Word::ZERO), Word::ZERO);
+    }
+
+    #[test]
+    fn test_sstore_dirty() {
+        let mut world_state = OnMemoryWorldState::default();
+        let mut account = Account::default();
+        account.storage.0.insert(Word::ZERO, Word::from(U256::from(1)));
+        world_state.insert(Address::default(), account);
+        let message = call_message(&Address::default(), &[], 100000);
+        let mut host = Host::new(world_state, TransactionContext::default());
+        // SSTORE(1, 1), SSTORE(0, 0), SSTORE(0, 1)
+        let ctx = execute_message(vec![
+            0x60, 0x01, 0x60, 0x01, 0x55,
+            0x60, 0x00, 0x60, 0x00, 0x55,
+            0x60, 0x01, 0x60, 0x00, 0x55], &message, &mut host);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.used_gas, 3 * 6 + 20000 + 5000 + 800);
+        // 15000 for the deletion is cancelled, and 5000 - 800 is refunded for the restoration.
+        assert_eq!(ctx.refund_gas, 4200);
+    }
+
+    #[test]
+    fn test_log() {
+        let mut world_state = OnMemoryWorldState::default();
+        world_state.insert(Address::default(), Account::default());
+        let mut message = call_message(&Address::from_hex("00000000000000000000000000000000000000aa").unwrap(), &[], 100000);
+        // MSTORE(0, 0x11223344), LOG2(28, 4, 0xaa, 0xbb), LOG0(0, 0)
+        let codes = vec![
+            0x63, 0x11, 0x22, 0x33, 0x44, 0x60, 0x00, 0x52,
+            0x60, 0xbb, 0x60, 0xaa, 0x60, 0x04, 0x60, 0x1c, 0xa2,
+            0x60, 0x00, 0x60, 0x00, 0xa0];
+        let mut host = Host::new(world_state, TransactionContext::default());
+        let ctx = execute_message(codes.clone(), &message, &mut host);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.used_gas, 3 + 3 + 6 + 3 * 4 + 375 * 3 + 8 * 4 + 3 + 3 + 375);
+        let logs = host.take_logs();
+        assert_eq!(ctx.logs(), &logs[..]);
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].address, message.destination);
+        assert_eq!(logs[0].topics, vec![Word::from(U256::from(0xaa)), Word::from(U256::from(0xbb))]);
+        assert_eq!(logs[0].data, vec![0x11, 0x22, 0x33, 0x44]);
+        assert!(logs[1].topics.is_empty());
+        assert!(logs[1].data.is_empty());
+
+        message.is_static = true;
+        let ctx = execute_message(codes, &message, &mut host);
+        assert_eq!(ctx.state, ContextState::StaticModeViolation);
+        assert!(host.logs().is_empty());
+    }
+
+    #[test]
+    fn test_call_logs() {
+        let address_b = Address::from_hex("00000000000000000000000000000000000000bb").unwrap();
+        let address_c = Address::from_hex("00000000000000000000000000000000000000cc").unwrap();
+        let mut world_state = OnMemoryWorldState::default();
+        world_state.insert(address_b.clone(), Account {
+            address: address_b.clone(),
+            // LOG0(0, 0)
+            code: vec![0x60, 0x00, 0x60, 0x00, 0xa0],
+            .. Account::default()
+        });
+        world_state.insert(address_c.clone(), Account {
+            address: address_c.clone(),
+            // LOG0(0, 0), REVERT(0, 0)
+            code: vec![0x60, 0x00, 0x60, 0x00, 0xa0, 0x60, 0x00, 0x60, 0x00, 0xfd],
+            .. Account::default()
+        });
+        let message = call_message(&Address::from_hex("00000000000000000000000000000000000000aa").unwrap(), &[], 100000);
+        // CALL(0xffff, 0xbb, 0, 0, 0, 0, 0), CALL(0xffff, 0xcc, 0, 0, 0, 0, 0), LOG1(0, 0, 1)
+        let codes = vec![
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00,
+            0x60, 0x00, 0x60, 0xbb, 0x61, 0xff, 0xff, 0xf1, 0x50,
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00,
+            0x60, 0x00, 0x60, 0xcc, 0x61, 0xff, 0xff, 0xf1, 0x50,
+            0x60, 0x01, 0x60, 0x00, 0x60, 0x00, 0xa1];
+        let mut host = Host::new(world_state, TransactionContext::default());
+        let ctx = execute_message(codes, &message, &mut host);
+        assert_eq!(ctx.state, ContextState::Success);
+        // the log of the reverted call is not included.
+        let logs = ctx.logs();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].address, address_b);
+        assert_eq!(logs[1].address, message.destination);
+        assert_eq!(logs[1].topics, vec![Word::from(U256::from(1))]);
+        assert_eq!(logs, host.logs());
+    }
+
+    #[test]
+    fn test_call() {
+        let caller = Address::from_hex("00000000000000000000000000000000000000cc").unwrap();
+        let address_a = Address::from_hex("00000000000000000000000000000000000000aa").unwrap();
+        let address_b = Address::from_hex("00000000000000000000000000000000000000bb").unwrap();
+        let mut world_state = OnMemoryWorldState::default();
+        world_state.insert(address_a.clone(), Account {
+            address: address_a.clone(),
+            balance: U256::from(100),
+            code: vec![
+                // CALL(0xffff, 0xbb, 5, 0, 0, 0, 32), SSTORE(2, result), RETURN(0, 32)
+                0x60, 0x20, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00,
+                0x60, 0x05, 0x60, 0xbb, 0x61, 0xff, 0xff, 0xf1,
+                0x60, 0x02, 0x55,
+                0x60, 0x20, 0x60, 0x00, 0xf3],
+            .. Account::default()
+        });
+        // SSTORE(0, CALLER), SSTORE(1, CALLVALUE), MSTORE(0, ADDRESS), RETURN(0, 32)
+        world_state.insert(address_b.clone(), Account {
+            address: address_b.clone(),
+            code: vec![
+                0x33, 0x60, 0x00, 0x55,
+                0x34, 0x60, 0x01, 0x55,
+                0x30, 0x60, 0x00, 0x52,
+                0x60, 0x20, 0x60, 0x00, 0xf3],
+            .. Account::default()
+        });
+        let mut host = Host::new(world_state, TransactionContext::default());
+        let message = CallMessage {
+            sender: caller.clone(),
+            .. call_message(&address_a, &[], 100000)
+        };
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::Success);
+        assert_eq!(result.output, Word::from(&address_b).as_ref());
+        assert_eq!(host.get_storage(&address_b, &Word::ZERO), Word::from(&address_a));
+        assert_eq!(host.get_storage(&address_b, &Word::from(U256::from(1))), Word::from(U256::from(5)));
+        assert_eq!(host.get_storage(&address_a, &Word::from(U256::from(2))), Word::from(U256::from(1)));
+        assert_eq!(host.get_balance(&address_a), U256::from(95));
+        assert_eq!(host.get_balance(&address_b), U256::from(5));
+    }
+
+    #[test]
+    fn test_call_gas() {
+        let caller = Address::from_hex("00000000000000000000000000000000000000cc").unwrap();
+        let address_a = Address::from_hex("00000000000000000000000000000000000000aa").unwrap();
+        let address_b = Address::from_hex("00000000000000000000000000000000000000bb").unwrap();
+        let mut world_state = OnMemoryWorldState::default();
+        world_state.insert(address_a.clone(), Account {
+            address: address_a.clone(),
+            balance: U256::from(100),
+            code: vec![],
+            .. Account::default()
+        });
+        // SSTORE(0, CALLER), SSTORE(1, CALLVALUE), MSTORE(0, ADDRESS), RETURN(0, 32)
+        world_state.insert(address_b.clone(), Account {
+            address: address_b.clone(),
+            code: vec![
+                0x33, 0x60, 0x00, 0x55,
+                0x34, 0x60, 0x01, 0x55,
+                0x30, 0x60, 0x00, 0x52,
+                0x60, 0x20, 0x60, 0x00, 0xf3],
+            .. Account::default()
+        });
+        let mut host = Host::new(world_state, TransactionContext::default());
+        let message = CallMessage {
+            sender: caller.clone(),
+            .. call_message(&address_a, &[], 100000)
+        };
+        let empty = Address::from_hex("00000000000000000000000000000000000000ee").unwrap();
+        // CALL(0xffff, 0xee, 0, 0, 0, 0, 0): the callee has no code and the gas is returned.
+        let codes = vec![
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00,
+            0x60, 0x00, 0x60, 0xee, 0x61, 0xff, 0xff, 0xf1];
+        let ctx = execute_message(codes, &message, &mut host);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.used_gas, 3 * 7 + 700);
+
+        // with value to the account which does not exist. the stipend is given to the callee.
+        let codes = vec![
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00,
+            0x60, 0x01, 0x60, 0xee, 0x60, 0x00, 0xf1];
+        let mut ctx = execute_message(codes, &message, &mut host);
+        assert_eq!(ctx.state, ContextState::Success);
+        // the stipend is not used and returned.
+        assert_eq!(ctx.used_gas, 3 * 7 + 700 + 9000 + 25000 - 2300);
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(1));
+        assert_eq!(host.get_balance(&empty), U256::from(1));
+
+        // only 63/64 of the gas left is given.
+        let mut message = message.clone();
+        message.destination = address_b.clone();
+        message.code_address = address_b.clone();
+        message.gas = 3 * 7 + 700 + 6400;
+        let codes = vec![
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00,
+            0x60, 0x00, 0x60, 0xbb, 0x61, 0xff, 0xff, 0xf1];
+        let ctx = execute_message(codes, &message, &mut host);
+        assert_eq!(ctx.state, ContextState::Success);
+        // the callee runs out of gas, so 100 gas is left.
+        assert_eq!(ctx.gas_left(), 100);
+    }
+
+    #[test]
+    fn test_call_failure() {
+        let caller = Address::from_hex("00000000000000000000000000000000000000cc").unwrap();
+        let address_a = Address::from_hex("00000000000000000000000000000000000000aa").unwrap();
+        let address_b = Address::from_hex("00000000000000000000000000000000000000bb").unwrap();
+        let mut world_state = OnMemoryWorldState::default();
+        world_state.insert(address_a.clone(), Account {
+            address: address_a.clone(),
+            balance: U256::from(100),
+            code: vec![],
+            .. Account::default()
+        });
+        // SSTORE(0, CALLER), SSTORE(1, CALLVALUE), MSTORE(0, ADDRESS), RETURN(0, 32)
+        world_state.insert(address_b.clone(), Account {
+            address: address_b.clone(),
+            code: vec![
+                0x33, 0x60, 0x00, 0x55,
+                0x34, 0x60, 0x01, 0x55,
+                0x30, 0x60, 0x00, 0x52,
+                0x60, 0x20, 0x60, 0x00, 0xf3],
+            .. Account::default()
+        });
+        let mut host = Host::new(world_state, TransactionContext::default());
+        let message = CallMessage {
+            sender: caller.clone(),
+            .. call_message(&address_a, &[], 100000)
+        };
+        // CALL(0xffff, 0xbb, 1000, 0, 0, 0, 0): the balance is not enough.
+        let codes = vec![
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00,
+            0x61, 0x03, 0xe8, 0x60, 0xbb, 0x61, 0xff, 0xff, 0xf1];
+        let mut ctx = execute_message(codes, &message, &mut host);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.used_gas, 3 * 7 + 700 + 9000 - 2300);
+        assert_eq!(ctx.stack.pop().unwrap(), Word::ZERO);
+        assert_eq!(host.get_storage(&address_b, &Word::ZERO), Word::ZERO);
+
+        let mut message = message.clone();
+        message.depth = 1025;
+        let result = execute_call(&mut host, &message, Hardfork::default(), &JUMP_TABLE, &mut NoopTracer);
+        assert_eq!(result.state, ContextState::Revert);
+        assert_eq!(result.gas_left, 100000);
+    }
+
+    #[test]
+    fn test_delegatecall() {
+        let caller = Address::from_hex("00000000000000000000000000000000000000cc").unwrap();
+        let address_a = Address::from_hex("00000000000000000000000000000000000000aa").unwrap();
+        let address_b = Address::from_hex("00000000000000000000000000000000000000bb").unwrap();
+        let mut world_state = OnMemoryWorldState::default();
+        world_state.insert(address_a.clone(), Account {
+            address: address_a.clone(),
+            balance: U256::from(100),
+            code: vec![
+                // DELEGATECALL(0xffff, 0xbb, 0, 0, 0, 32), RETURN(0, 32)
+                0x60, 0x20, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00,
+                0x60, 0xbb, 0x61, 0xff, 0xff, 0xf4,
+                0x60, 0x20, 0x60, 0x00, 0xf3],
+            .. Account::default()
+        });
+        // SSTORE(0, CALLER), SSTORE(1, CALLVALUE), MSTORE(0, ADDRESS), RETURN(0, 32)
+        world_state.insert(address_b.clone(), Account {
+            address: address_b.clone(),
+            code: vec![
+                0x33, 0x60, 0x00, 0x55,
+                0x34, 0x60, 0x01, 0x55,
+                0x30, 0x60, 0x00, 0x52,
+                0x60, 0x20, 0x60, 0x00, 0xf3],
+            .. Account::default()
+        });
+        let mut host = Host::new(world_state, TransactionContext::default());
+        let message = CallMessage {
+            sender: caller.clone(),
+            .. call_message(&address_a, &[], 100000)
+        };
+        host.world_state_mut().insert(caller.clone(), Account {
+            address: caller.clone(),
+            balance: U256::from(100),
+            .. Account::default()
+        });
+        let mut message = message.clone();
+        message.value = U256::from(7);
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::Success);
+        assert_eq!(result.output, Word::from(&address_a).as_ref());
+        assert_eq!(host.get_storage(&address_a, &Word::ZERO), Word::from(&caller));
+        assert_eq!(host.get_storage(&address_a, &Word::from(U256::from(1))), Word::from(U256::from(7)));
+        assert_eq!(host.get_storage(&address_b, &Word::ZERO), Word::ZERO);
+    }
+
+    #[test]
+    fn test_callcode() {
+        let caller = Address::from_hex("00000000000000000000000000000000000000cc").unwrap();
+        let address_a = Address::from_hex("00000000000000000000000000000000000000aa").unwrap();
+        let address_b = Address::from_hex("00000000000000000000000000000000000000bb").unwrap();
+        let mut world_state = OnMemoryWorldState::default();
+        world_state.insert(address_a.clone(), Account {
+            address: address_a.clone(),
+            balance: U256::from(100),
+            code: vec![
+                // CALLCODE(0xffff, 0xbb, 5, 0, 0, 0, 32), RETURN(0, 32)
+                0x60, 0x20, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00,
+                0x60, 0x05, 0x60, 0xbb, 0x61, 0xff, 0xff, 0xf2,
+                0x60, 0x20, 0x60, 0x00, 0xf3],
+            .. Account::default()
+        });
+        // SSTORE(0, CALLER), SSTORE(1, CALLVALUE), MSTORE(0, ADDRESS), RETURN(0, 32)
+        world_state.insert(address_b.clone(), Account {
+            address: address_b.clone(),
+            code: vec![
+                0x33, 0x60, 0x00, 0x55,
+                0x34, 0x60, 0x01, 0x55,
+                0x30, 0x60, 0x00, 0x52,
+                0x60, 0x20, 0x60, 0x00, 0xf3],
+            .. Account::default()
+        });
+        let mut host = Host::new(world_state, TransactionContext::default());
+        let message = CallMessage {
+            sender: caller.clone(),
+            .. call_message(&address_a, &[], 100000)
+        };
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::Success);
+        assert_eq!(result.output, Word::from(&address_a).as_ref());
+        assert_eq!(host.get_storage(&address_a, &Word::ZERO), Word::from(&address_a));
+        assert_eq!(host.get_storage(&address_a, &Word::from(U256::from(1))), Word::from(U256::from(5)));
+        assert_eq!(host.get_balance(&address_a), U256::from(100));
+    }
+
+    #[test]
+    fn test_staticcall() {
+        let caller = Address::from_hex("00000000000000000000000000000000000000cc").unwrap();
+        let address_a = Address::from_hex("00000000000000000000000000000000000000aa").unwrap();
+        let address_b = Address::from_hex("00000000000000000000000000000000000000bb").unwrap();
+        let mut world_state = OnMemoryWorldState::default();
+        world_state.insert(address_a.clone(), Account {
+            address: address_a.clone(),
+            balance: U256::from(100),
+            code: vec![
+                // STATICCALL(0xffff, 0xbb, 0, 0, 0, 32), SSTORE(2, result)
+                0x60, 0x20, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00,
+                0x60, 0xbb, 0x61, 0xff, 0xff, 0xfa,
+                0x60, 0x02, 0x55],
+            .. Account::default()
+        });
+        // SSTORE(0, CALLER), SSTORE(1, CALLVALUE), MSTORE(0, ADDRESS), RETURN(0, 32)
+        world_state.insert(address_b.clone(), Account {
+            address: address_b.clone(),
+            code: vec![
+                0x33, 0x60, 0x00, 0x55,
+                0x34, 0x60, 0x01, 0x55,
+                0x30, 0x60, 0x00, 0x52,
+                0x60, 0x20, 0x60, 0x00, 0xf3],
+            .. Account::default()
+        });
+        let mut host = Host::new(world_state, TransactionContext::default());
+        let message = CallMessage {
+            sender: caller.clone(),
+            .. call_message(&address_a, &[], 100000)
+        };
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::Success);
+        // SSTORE in the callee is a static mode violation.
+        assert_eq!(host.get_storage(&address_a, &Word::from(U256::from(2))), Word::ZERO);
+        assert_eq!(host.get_storage(&address_b, &Word::ZERO), Word::ZERO);
+        // all the given gas is consumed by the violation.
+        assert_eq!(result.gas_left, 100000 - (3 * 6 + 3 + 700 + 0xffff + 3 + 800));
+    }
+
+    #[test]
+    fn test_create() {
+        let caller = Address::from_hex("00000000000000000000000000000000000000cc").unwrap();
+        let address_a = Address::from_hex("00000000000000000000000000000000000000aa").unwrap();
+        // SSTORE(0, 1), MSTORE8(0, 2), RETURN(0, 1)
+        let init_code = [
+            0x60, 0x01, 0x60, 0x00, 0x55,
+            0x60, 0x02, 0x60, 0x00, 0x53,
+            0x60, 0x01, 0x60, 0x00, 0xf3];
+        let mut codes = vec![0x6e];
+        codes.extend_from_slice(&init_code);
+        codes.extend_from_slice(&[
+            // MSTORE(0, init code), SSTORE(0, CREATE(10, 17, 15))
+            0x60, 0x00, 0x52,
+            0x60, 0x0f, 0x60, 0x11, 0x60, 0x0a, 0xf0,
+            0x60, 0x00, 0x55,
+            // SSTORE(1, CREATE2(0, 17, 15, 1))
+            0x60, 0x01, 0x60, 0x0f, 0x60, 0x11, 0x60, 0x00, 0xf5,
+            0x60, 0x01, 0x55]);
+        let mut world_state = OnMemoryWorldState::default();
+        world_state.insert(address_a.clone(), Account {
+            address: address_a.clone(),
+            balance: U256::from(100),
+            code: codes,
+            .. Account::default()
+        });
+        let mut host = Host::new(world_state.clone(), TransactionContext::default());
+        let message = CallMessage {
+            sender: caller.clone(),
+            .. call_message(&address_a, &[], 1000000)
+        };
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::Success);
+
+        let created = Address::create(&address_a, 0);
+        let created2 = Address::create2(&address_a, &Word::from(U256::from(1)), &init_code);
+        assert_eq!(host.get_storage(&address_a, &Word::ZERO), Word::from(&created));
+        assert_eq!(host.get_storage(&address_a, &Word::from(U256::from(1))), Word::from(&created2));
+        assert_eq!(host.get_nonce(&address_a), 2);
+        assert_eq!(host.get_balance(&address_a), U256::from(90));
+        assert_eq!(host.get_balance(&created), U256::from(10));
+        for address in &[created.clone(), created2] {
+            assert_eq!(host.get_nonce(address), 1);
+            assert_eq!(host.get_code_size(address), 1);
+            assert_eq!(host.get_storage(address, &Word::ZERO), Word::from(U256::from(1)));
+        }
+
+        // the nonce of the new contract starts from 0 before Spurious Dragon (EIP-161).
+        let message = CallMessage {
+            kind: CallKind::Create,
+            sender: address_a.clone(),
+            .. call_message(&Address::default(), &init_code, 1000000)
+        };
+        let mut host = Host::new(world_state, TransactionContext::default()).with_hardfork(Hardfork::Homestead);
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::Success);
+        assert_eq!(result.create_address, created);
+        assert_eq!(host.get_nonce(&created), 0);
+        assert_eq!(host.get_code_size(&created), 1);
+    }
+
+    #[test]
+    fn test_create_failure() {
+        let address_a = Address::from_hex("00000000000000000000000000000000000000aa").unwrap();
+        let mut world_state = OnMemoryWorldState::default();
+        world_state.insert(address_a.clone(), Account {
+            address: address_a.clone(),
+            balance: U256::from(100),
+            .. Account::default()
+        });
+        let mut message = CallMessage {
+            kind: CallKind::Create,
+            sender: address_a.clone(),
+            .. call_message(&Address::default(), &[], 100000)
+        };
+        let mut host = Host::new(world_state, TransactionContext::default()).with_hardfork(Hardfork::London);
+
+        // MSTORE8(0, 0xef), RETURN(0, 1): the code starting with 0xEF is rejected.
+        let init_code = [0x60, 0xef, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xf3];
+        message.input_data = &init_code;
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::InvalidCode);
+        assert_eq!(result.gas_left, 0);
+        assert_eq!(result.create_address, Address::default());
+        assert_eq!(host.get_code_size(&Address::create(&address_a, 0)), 0);
+
+        // RETURN(0, 24577): the code is too large.
+        let init_code = [0x61, 0x60, 0x01, 0x60, 0x00, 0xf3];
+        message.input_data = &init_code;
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::CodeSizeExceeded);
+
+        // RETURN(0, 1000): the gas is not enough to deposit the code.
+        let init_code = [0x61, 0x03, 0xe8, 0x60, 0x00, 0xf3];
+        message.input_data = &init_code;
+        message.gas = 100000 / 2;
+        message.value = U256::from(7);
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::OutOfGas);
+        assert_eq!(host.get_balance(&address_a), U256::from(100));
+        message.gas = 300000;
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::Success);
+        assert_eq!(result.create_address, Address::create(&address_a, 3));
+        assert_eq!(host.get_code_size(&result.create_address), 1000);
+        assert_eq!(host.get_balance(&result.create_address), U256::from(7));
+        assert_eq!(host.get_nonce(&address_a), 4);
+
+        // the same salt and init code make the same address.
+        message.kind = CallKind::Create2;
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::Success);
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::CreateCollision);
+        assert_eq!(host.get_balance(&address_a), U256::from(86));
+    }
+
+    #[test]
+    fn test_selfdestruct() {
+        let caller = Address::from_hex("00000000000000000000000000000000000000cc").unwrap();
+        let address_a = Address::from_hex("00000000000000000000000000000000000000aa").unwrap();
+        let beneficiary = Address::from_hex("00000000000000000000000000000000000000bb").unwrap();
+        let mut world_state = OnMemoryWorldState::default();
+        // SELFDESTRUCT(0xbb)
+        world_state.insert(address_a.clone(), Account {
+            address: address_a.clone(),
+            balance: U256::from(100),
+            code: vec![0x60, 0xbb, 0xff],
+            .. Account::default()
+        });
+        let mut message = CallMessage {
+            sender: caller.clone(),
+            .. call_message(&address_a, &[], 100000)
+        };
+        let mut host = Host::new(world_state.clone(), TransactionContext::default());
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::Success);
+        // the beneficiary does not exist.
+        assert_eq!(result.gas_left, 100000 - (3 + 5000 + 25000));
+        // 24000 is capped by the half of the used gas.
+        assert_eq!(result.refund_gas, (3 + 5000 + 25000) / 2);
+        assert_eq!(host.get_balance(&beneficiary), U256::from(100));
+        assert_eq!(host.get_balance(&address_a), U256::zero());
+        assert!(host.account_exists(&address_a));
+        host.world_state_mut().finalize_transaction();
+        assert!(!host.account_exists(&address_a));
+
+        // the account created in the previous transaction is not deleted after Cancun.
+        let mut host = Host::new(world_state.clone(), TransactionContext::default()).with_hardfork(Hardfork::Cancun);
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::Success);
+        assert_eq!(result.refund_gas, 0);
+        assert_eq!(host.get_balance(&beneficiary), U256::from(100));
+        host.world_state_mut().finalize_transaction();
+        assert!(host.account_exists(&address_a));
+        assert_eq!(host.get_code_size(&address_a), 3);
+
+        // the account created in the same transaction is deleted.
+        let init_code = [0x60, 0xbb, 0xff];
+        message.kind = CallKind::Create;
+        message.sender = address_a.clone();
+        message.input_data = &init_code;
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::Success);
+        let created = Address::create(&address_a, 0);
+        assert!(host.account_exists(&created));
+        host.world_state_mut().finalize_transaction();
+        assert!(!host.account_exists(&created));
+
+        message.is_static = true;
+        message.kind = CallKind::Call;
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::StaticModeViolation);
+    }
+
+    #[test]
+    fn test_return_data() {
+        let caller = Address::from_hex("00000000000000000000000000000000000000cc").unwrap();
+        let address_a = Address::from_hex("00000000000000000000000000000000000000aa").unwrap();
+        let address_b = Address::from_hex("00000000000000000000000000000000000000bb").unwrap();
+        let mut world_state = OnMemoryWorldState::default();
+        world_state.insert(address_a.clone(), Account {
+            address: address_a.clone(),
+            .. Account::default()
+        });
+        // MSTORE(0, ADDRESS), RETURN(0, 32)
+        world_state.insert(address_b.clone(), Account {
+            address: address_b.clone(),
+            code: vec![0x30, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3],
+            .. Account::default()
+        });
+        let mut host = Host::new(world_state, TransactionContext::default());
+        let message = CallMessage {
+            sender: caller.clone(),
+            .. call_message(&address_a, &[], 100000)
+        };
+        // RETURNDATASIZE, CALL(0xffff, 0xbb, 0, 0, 0, 0, 0), RETURNDATASIZE, RETURNDATACOPY(0, 0, 32)
+        let codes = vec![
+            0x3d,
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00,
+            0x60, 0x00, 0x60, 0xbb, 0x61, 0xff, 0xff, 0xf1,
+            0x3d,
+            0x60, 0x20, 0x60, 0x00, 0x60, 0x00, 0x3e];
+        let mut ctx = execute_message(codes.clone(), &message, &mut host);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.return_data_buffer(), Word::from(&address_b).as_ref());
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(32));
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(1));
+        assert_eq!(U256::from(ctx.stack.pop().unwrap()), U256::from(0));
+        assert_eq!(ctx.memory.read(0).unwrap(), Word::from(&address_b));
+
+        // RETURNDATACOPY(0, 1, 32) reads out of the buffer.
+        let mut codes = codes;
+        codes.extend_from_slice(&[0x60, 0x20, 0x60, 0x01, 0x60, 0x00, 0x3e]);
+        let ctx = execute_message(codes, &message, &mut host);
+        assert_eq!(ctx.state, ContextState::ReturnDataOutOfBounds);
+        assert_eq!(ctx.gas_left(), 0);
+    }
+
+    #[test]
+    fn test_revert_call() {
+        let caller = Address::from_hex("00000000000000000000000000000000000000cc").unwrap();
+        let address_a = Address::from_hex("00000000000000000000000000000000000000aa").unwrap();
+        let address_b = Address::from_hex("00000000000000000000000000000000000000bb").unwrap();
+        let address_c = Address::from_hex("00000000000000000000000000000000000000dd").unwrap();
+        let mut world_state = OnMemoryWorldState::default();
+        world_state.insert(address_a.clone(), Account {
+            address: address_a.clone(),
+            balance: U256::from(100),
+            .. Account::default()
+        });
+        // SSTORE(0, 1), LOG0(0, 0), CALL(0xffff, 0xdd, 0, 0, 0, 0, 0), REVERT(0, 0)
+        world_state.insert(address_b.clone(), Account {
+            address: address_b.clone(),
+            code: vec![
+                0x60, 0x01, 0x60, 0x00, 0x55,
+                0x60, 0x00, 0x60, 0x00, 0xa0,
+                0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00,
+                0x60, 0x00, 0x60, 0xdd, 0x61, 0xff, 0xff, 0xf1,
+                0x60, 0x00, 0x60, 0x00, 0xfd],
+            .. Account::default()
+        });
+        // SSTORE(0, 2)
+        world_state.insert(address_c.clone(), Account {
+            address: address_c.clone(),
+            code: vec![0x60, 0x02, 0x60, 0x00, 0x55],
+            .. Account::default()
+        });
+        let mut host = Host::new(world_state, TransactionContext::default());
+        let message = CallMessage {
+            sender: caller.clone(),
+            .. call_message(&address_a, &[], 100000)
+        };
+        // CALL(0xffff, 0xbb, 5, 0, 0, 0, 0)
+        let codes = vec![
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00,
+            0x60, 0x05, 0x60, 0xbb, 0x61, 0xff, 0xff, 0xf1];
+        let mut ctx = execute_message(codes, &message, &mut host);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.stack.pop().unwrap(), Word::ZERO);
+        // the changes of B and the successful call to C are reverted.
+        assert_eq!(host.get_storage(&address_b, &Word::ZERO), Word::ZERO);
+        assert_eq!(host.get_storage(&address_c, &Word::ZERO), Word::ZERO);
+        assert!(host.logs().is_empty());
+        assert_eq!(host.get_balance(&address_a), U256::from(100));
+        assert_eq!(host.get_balance(&address_b), U256::zero());
+    }
+
+    #[test]
+    fn test_access_gas() {
+        let address = Address::from_hex("00000000000000000000000000000000000000aa").unwrap();
+        let message = call_message(&address, &[], 100000);
+        // SLOAD(0) twice, EXTCODESIZE(0xbb) twice, CALL(0xffff, 0xbb, 0, 0, 0, 0, 0) twice, SSTORE(1, 1)
+        let codes = vec![
+            0x60, 0x00, 0x54, 0x60, 0x00, 0x54,
+            0x60, 0xbb, 0x3b, 0x60, 0xbb, 0x3b,
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00,
+            0x60, 0x00, 0x60, 0xbb, 0x61, 0xff, 0xff, 0xf1,
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00,
+            0x60, 0x00, 0x60, 0xbb, 0x61, 0xff, 0xff, 0xf1,
+            0x60, 0x01, 0x60, 0x01, 0x55];
+        let mut world_state = OnMemoryWorldState::default();
+        world_state.insert(address.clone(), Account {
+            address: address.clone(),
+            .. Account::default()
+        });
+        let mut host = Host::new(world_state.clone(), TransactionContext::default());
+        let ctx = interpret(
+            Context::with_message(codes.clone(), &message).with_hardfork(Hardfork::Berlin),
+            &JUMP_TABLE, &mut host, &mut NoopTracer);
+        assert_eq!(ctx.state, ContextState::Success);
+        // the first access is cold, and the rest are warm. 0xbb is warmed by EXTCODESIZE before CALL.
+        assert_eq!(ctx.used_gas, 3 + 2100 + 3 + 100 + 3 + 2600 + 3 + 100 + 21 + 100 + 21 + 100 + 3 + 3 + 2100 + 20000);
+
+        let mut host = Host::new(world_state.clone(), TransactionContext::default());
+        let ctx = execute_message(codes, &message, &mut host);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.used_gas, 3 + 800 + 3 + 800 + 3 + 700 + 3 + 700 + 21 + 700 + 21 + 700 + 3 + 3 + 20000);
+
+        // the recipient of the transaction is already warm.
+        world_state.insert(address.clone(), Account {
+            address: address.clone(),
+            // EXTCODESIZE(ADDRESS)
+            code: vec![0x30, 0x3b],
+            .. Account::default()
+        });
+        let mut host = Host::new(world_state, TransactionContext::default()).with_hardfork(Hardfork::Berlin);
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::Success);
+        assert_eq!(result.gas_left, 100000 - 2 - 100);
+    }
+
+    #[test]
+    fn test_access_list() {
+        let address = Address::from_hex("00000000000000000000000000000000000000aa").unwrap();
+        let mut world_state = OnMemoryWorldState::default();
+        // SLOAD(0), EXTCODESIZE(0xbb)
+        world_state.insert(address.clone(), Account {
+            address: address.clone(),
+            code: vec![0x60, 0x00, 0x54, 0x60, 0xbb, 0x3b],
+            .. Account::default()
+        });
+        let access_list = AccessList(vec![
+            AccessListItem {
+                address: address.clone(),
+                storage_keys: vec![Word::ZERO],
+            },
+            AccessListItem {
+                address: Address::from_hex("00000000000000000000000000000000000000bb").unwrap(),
+                storage_keys: vec![],
+            },
+        ]);
+        let mut host = Host::new(world_state.clone(), TransactionContext::default())
+            .with_hardfork(Hardfork::Berlin)
+            .with_access_list(access_list.clone());
+        let mut message = call_message(&address, &[], 100000);
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::Success);
+        // the listed slot and account are warm.
+        assert_eq!(result.gas_left, 100000 - 2400 * 2 - 1900 - 3 - 100 - 3 - 100);
+
+        // the gas is not enough for the access list.
+        host.world_state_mut().finalize_transaction();
+        message.gas = 2400 * 2 + 1900 - 1;
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::OutOfGas);
+        assert_eq!(result.gas_left, 0);
+
+        // the access list is not charged before Berlin.
+        let mut host = Host::new(world_state, TransactionContext::default())
+            .with_hardfork(Hardfork::Istanbul)
+            .with_access_list(access_list);
+        message.gas = 100000;
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::Success);
+        assert_eq!(result.gas_left, 100000 - 3 - 800 - 3 - 700);
+        message.gas = -1;
+        assert_eq!(host.call(&message).state, ContextState::OutOfGas);
+
+        // SSTORE(0, 0) to the listed slot. the refund is capped by the gas including the access list.
+        let mut account = Account {
+            address: address.clone(),
+            code: vec![0x60, 0x00, 0x60, 0x00, 0x55],
+            .. Account::default()
+        };
+        account.storage.0.insert(Word::ZERO, Word::from(U256::from(1)));
+        let mut world_state = OnMemoryWorldState::default();
+        world_state.insert(address.clone(), account);
+        let mut host = Host::new(world_state, TransactionContext::default())
+            .with_hardfork(Hardfork::London)
+            .with_access_list(AccessList(vec![AccessListItem { address: address.clone(), storage_keys: vec![Word::ZERO] }]));
+        message.gas = 100000;
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::Success);
+        assert_eq!(result.refund_gas, (2400 + 1900 + 3 + 3 + 2900) / 5);
+    }
+
+    #[test]
+    fn test_hardfork() {
+        let mut world_state = OnMemoryWorldState::default();
+        world_state.insert(Address::default(), Account::default());
+        let message = call_message(&Address::default(), &[], 100000);
+        let run = |codes: Vec<u8>, hardfork: Hardfork| {
+            let mut host = Host::new(world_state.clone(), TransactionContext::default());
+            interpret(Context::with_message(codes, &message).with_hardfork(hardfork), &JUMP_TABLE, &mut host, &mut NoopTracer)
+        };
+
+        // SHL(1, 1) is introduced in Constantinople.
+        let shl = vec![0x60, 0x01, 0x60, 0x01, 0x1b];
+        assert_eq!(run(shl.clone(), Hardfork::Byzantium).state, ContextState::Invalid);
+        assert_eq!(run(shl, Hardfork::Constantinople).state, ContextState::Success);
+        // BASEFEE is introduced in London.
+        assert_eq!(run(vec![0x48], Hardfork::Berlin).state, ContextState::Invalid);
+        assert_eq!(run(vec![0x48], Hardfork::London).state, ContextState::Success);
+
+        // SLOAD(0)
+        let sload = vec![0x60, 0x00, 0x54];
+        assert_eq!(run(sload.clone(), Hardfork::Frontier).used_gas, 3 + 50);
+        assert_eq!(run(sload.clone(), Hardfork::TangerineWhistle).used_gas, 3 + 200);
+        assert_eq!(run(sload.clone(), Hardfork::Istanbul).used_gas, 3 + 800);
+        assert_eq!(run(sload, Hardfork::Berlin).used_gas, 3 + 2100);
+        // BALANCE(0)
+        let balance = vec![0x60, 0x00, 0x31];
+        assert_eq!(run(balance.clone(), Hardfork::Frontier).used_gas, 3 + 20);
+        assert_eq!(run(balance.clone(), Hardfork::TangerineWhistle).used_gas, 3 + 400);
+        assert_eq!(run(balance.clone(), Hardfork::Istanbul).used_gas, 3 + 700);
+        assert_eq!(run(balance, Hardfork::Berlin).used_gas, 3 + 2600);
+        // EXP(2, 0x100)
+        let exp = vec![0x61, 0x01, 0x00, 0x60, 0x02, 0x0a];
+        assert_eq!(run(exp.clone(), Hardfork::Homestead).used_gas, 3 + 3 + 10 + 10 * 2);
+        assert_eq!(run(exp, Hardfork::SpuriousDragon).used_gas, 3 + 3 + 10 + 50 * 2);
+        // CALL(0xffff, 0, 0, 0, 0, 0, 0) to the existing account.
+        let call = vec![
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00,
+            0x60, 0x00, 0x60, 0x00, 0x61, 0xff, 0xff, 0xf1];
+        assert_eq!(run(call.clone(), Hardfork::Frontier).used_gas, 21 + 40);
+        assert_eq!(run(call, Hardfork::TangerineWhistle).used_gas, 21 + 700);
+
+        // SSTORE(0, 1), SSTORE(0, 2)
+        let sstore = vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x02, 0x60, 0x00, 0x55];
+        // without the net gas metering, the second one is charged as the reset.
+        assert_eq!(run(sstore.clone(), Hardfork::Byzantium).used_gas, 12 + 20000 + 5000);
+        assert_eq!(run(sstore.clone(), Hardfork::Constantinople).used_gas, 12 + 20000 + 200);
+        assert_eq!(run(sstore.clone(), Hardfork::Petersburg).used_gas, 12 + 20000 + 5000);
+        assert_eq!(run(sstore, Hardfork::Istanbul).used_gas, 12 + 20000 + 800);
+    }
+
+    #[test]
+    fn test_gas_table() {
+        let address = Address::from_hex("00000000000000000000000000000000000000aa").unwrap();
+        let mut world_state = OnMemoryWorldState::default();
+        // SLOAD(0), SHA3(0, 32), LOG0(0, 2)
+        let codes = vec![
+            0x60, 0x00, 0x54,
+            0x60, 0x20, 0x60, 0x00, 0x20,
+            0x60, 0x02, 0x60, 0x00, 0xa0];
+        world_state.insert(address.clone(), Account {
+            address: address.clone(),
+            code: codes.clone(),
+            .. Account::default()
+        });
+        let mut gas_table = GasTable {
+            memory_word_cost: 1,
+            sha3_word_cost: 10,
+            log_byte_cost: 100,
+            .. GasTable::default()
+        };
+        gas_table.static_costs.insert(String::from("SLOAD"), 5000);
+        gas_table.static_costs.insert(String::from("LOG0"), 1);
+        let mut table = JumpTable::default();
+        table.set_gas_table(gas_table);
+        let message = call_message(&address, &[], 100000);
+        let mut host = Host::new(world_state.clone(), TransactionContext::default());
+        let ctx = interpret(Context::with_message(codes.clone(), &message), &table, &mut host, &mut NoopTracer);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.used_gas, 3 + 5000 + 3 + 3 + 30 + 1 + 10 + 3 + 3 + 1 + 2 * 100);
+
+        // the nested frames use the same gas table.
+        // CALL(0xffff, 0xaa, 0, 0, 0, 0, 0)
+        let caller_codes = vec![
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00,
+            0x60, 0x00, 0x60, 0xaa, 0x61, 0xff, 0xff, 0xf1];
+        let mut host = Host::new(world_state.clone(), TransactionContext::default());
+        let default_ctx = execute_message(caller_codes.clone(), &message, &mut host);
+        let mut host = Host::new(world_state.clone(), TransactionContext::default());
+        let ctx = interpret(Context::with_message(caller_codes, &message), &table, &mut host, &mut NoopTracer);
+        assert_eq!(ctx.state, ContextState::Success);
+        assert_eq!(ctx.used_gas - default_ctx.used_gas, (5000 + 1 + 10 + 1 + 200) - (800 + 3 + 6 + 375 + 16));
+
+        // the host runs the transaction with the jump table.
+        let mut host = Host::new(world_state, TransactionContext::default()).with_jump_table(table);
+        let result = host.call(&message);
+        assert_eq!(result.state, ContextState::Success);
+        assert_eq!(result.gas_left, 100000 - (3 + 5000 + 3 + 3 + 30 + 1 + 10 + 3 + 3 + 1 + 2 * 100));
+    }
+
+    #[test]
+    fn test_gas_table_dynamic_costs() {
+        let address = Address::from_hex("00000000000000000000000000000000000000aa").unwrap();
+        let message = call_message(&address, &[], 100000);
+        let mut gas_table = GasTable {
+            sstore_set_cost: 1000,
+            cold_sload_cost: 200,
+            cold_account_access_cost: 300,
+            log_topic_cost: 10,
+            call_value_cost: 4000,
+            call_new_account_cost: 500,
+            .. GasTable::default()
+        };
+        gas_table.static_costs.insert(String::from("SSTORE"), 0);
+        let mut table = JumpTable::default();
+        table.set_gas_table(gas_table);
+        let run = |codes: Vec<u8>| {
+            let mut host = Host::new(OnMemoryWorldState::default(), TransactionContext::default());
+            interpret(Context::with_message(codes, &message).with_hardfork(Hardfork::Berlin), &table, &mut host, &mut NoopTracer)
+        };
+        // SSTORE(0, 1) to the cold slot.
+        assert_eq!(run(vec![0x60, 0x01, 0x60, 0x00, 0x55]).used_gas, 6 + 1000 + 200);
+        // SLOAD(0) to the cold slot.
+        assert_eq!(run(vec![0x60, 0x00, 0x54]).used_gas, 3 + 200);
+        // BALANCE(0xbb) of the cold account.
+        assert_eq!(run(vec![0x60, 0xbb, 0x31]).used_gas, 3 + 300);
+        // LOG2(0, 0, 0, 0)
+        assert_eq!(run(vec![0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0xa2]).used_gas, 12 + 375 + 2 * 10);
+        // CALL(0, 0xbb, 1, 0, 0, 0, 0) to the cold account which does not exist.
+        // the transfer fails without the balance, and the stipend is returned.
+        let ctx = run(vec![0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x01, 0x60, 0xbb, 0x60, 0x00, 0xf1]);
+        assert_eq!(ctx.used_gas, 21 + 300 + 4000 + 500 - 2300);
+    }
+}
@@ -0,0 +1,119 @@
+use std::io::Write;
+
+use crate::{Context, ContextState};
+use crate::core::{Stack, U256};
+use crate::hex_util::ToHex;
+use crate::instruction::{opcode_name, OpcodeFn};
+
+/// hooks called by the interpreter. all hooks do nothing by default.
+pub trait Tracer {
+    /// before the first instruction.
+    fn start(&mut self, _ctx: &Context) {}
+    /// before the instruction `op` at `ctx.pc()` is executed.
+    fn step_start(&mut self, _ctx: &Context, _op: &dyn OpcodeFn) {}
+    /// after the instruction is executed.
+    fn step_end(&mut self, _ctx: &Context) {}
+    /// the instruction pushed or popped the stack.
+    fn stack_changed(&mut self, _stack: &Stack) {}
+    /// the instruction wrote `data` into the memory at `offset`.
+    fn memory_changed(&mut self, _offset: u64, _data: &[u8]) {}
+    /// the execution halted with `ctx.state()`.
+    fn halt(&mut self, _ctx: &Context) {}
+}
+
+pub struct NoopTracer;
+
+impl Tracer for NoopTracer {}
+
+/// print the codes and the stack after every instruction to stdout.
+pub struct StackDumpTracer;
+
+impl Tracer for StackDumpTracer {
+    fn start(&mut self, ctx: &Context) {
+        println!("{:?}", ctx.codes());
+    }
+
+    fn step_end(&mut self, ctx: &Context) {
+        ctx.dump_stack();
+    }
+}
+
+///////////////////////////////////////////////
+//////////     EIP-3155 JSON Tracer   /////////
+///////////////////////////////////////////////
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StepLog {
+    pc: usize,
+    op: u8,
+    gas: String,
+    gas_cost: String,
+    mem_size: usize,
+    stack: Vec<String>,
+    depth: u32,
+    refund: i64,
+    op_name: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SummaryLog {
+    output: String,
+    gas_used: String,
+    pass: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// write an EIP-3155 trace, one JSON object per line, same as `evm --json` of geth.
+/// the summary line does not have `stateRoot`.
+pub struct JsonTracer<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> JsonTracer<W> {
+    pub fn new(writer: W) -> Self {
+        JsonTracer { writer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_line<T: serde::Serialize>(&mut self, log: &T) {
+        // tracing must not affect the execution, so io errors are ignored.
+        let _ = serde_json::to_writer(&mut self.writer, log);
+        let _ = self.writer.write_all(b"\n");
+    }
+}
+
+impl<W: Write> Tracer for JsonTracer<W> {
+    fn step_start(&mut self, ctx: &Context, _op: &dyn OpcodeFn) {
+        let opcode = ctx.codes()[ctx.pc()];
+        let log = StepLog {
+            pc: ctx.pc(),
+            op: opcode,
+            gas: format!("{:#x}", ctx.gas_left()),
+            gas_cost: format!("{:#x}", ctx.gas_cost()),
+            mem_size: ctx.memory().len(),
+            stack: ctx.stack().as_ref().iter().map(|w| format!("{:#x}", U256::from(*w))).collect(),
+            depth: ctx.depth() + 1,
+            refund: ctx.refund_gas(),
+            op_name: opcode_name(opcode),
+        };
+        self.write_line(&log);
+    }
+
+    fn halt(&mut self, ctx: &Context) {
+        if ctx.depth() > 0 {
+            return;
+        }
+        let log = SummaryLog {
+            output: ctx.return_data().to_hex(),
+            gas_used: format!("{:#x}", ctx.used_gas()),
+            pass: ctx.state() == &ContextState::Success,
+            error: if ctx.state().is_exceptional() { Some(format!("{:?}", ctx.state())) } else { None },
+        };
+        self.write_line(&log);
+    }
+}
@@ -0,0 +1,45 @@
+use nrs_evm::core::{AccessList, AccessListItem, Address, Word};
+use nrs_evm::hex_util::FromHex;
+
+#[test]
+fn test_access_list_from_json() {
+    let expect = AccessList(vec![
+        AccessListItem {
+            address: Address::from_hex("dd198a31e1dc7419aa5958097bffd6bdd1626ff1").unwrap(),
+            storage_keys: vec![
+                Word::from_hex("0000000000000000000000000000000000000000000000000000000000000001").unwrap(),
+            ],
+        },
+        AccessListItem {
+            address: Address::from_hex("00000000000000000000000000000000000000aa").unwrap(),
+            storage_keys: vec![],
+        },
+    ]);
+    let access_list: AccessList = serde_json::from_str(r#"
+    [
+        {
+            "address":"dd198a31e1dc7419aa5958097bffd6bdd1626ff1",
+            "storageKeys":["0000000000000000000000000000000000000000000000000000000000000001"]
+        },
+        {
+            "address":"00000000000000000000000000000000000000aa",
+            "storageKeys":[]
+        }
+    ]
+    "#).unwrap();
+    assert_eq!(expect, access_list);
+    assert_eq!(serde_json::from_str::<AccessList>(&serde_json::to_string(&access_list).unwrap()).unwrap(), expect);
+}
+
+#[test]
+fn test_intrinsic_gas() {
+    assert_eq!(AccessList::default().intrinsic_gas(), 0);
+    let access_list = AccessList(vec![
+        AccessListItem {
+            address: Address::default(),
+            storage_keys: vec![Word::ZERO, Word::ZERO],
+        },
+        AccessListItem::default(),
+    ]);
+    assert_eq!(access_list.intrinsic_gas(), 2400 * 2 + 1900 * 2);
+}
@@ -23,3 +23,26 @@ fn test_address_from_json() {
     let addr:Address = serde_json::from_str(r#""dd198a31e1dc7419aa5958097bffd6bdd1626ff1""#).unwrap();
     assert_eq!(address, addr);
 }
+
+#[test]
+fn test_create_address() {
+    let sender = Address::from_hex("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0").unwrap();
+    assert_eq!(Address::create(&sender, 0), Address::from_hex("cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d").unwrap());
+    assert_eq!(Address::create(&sender, 1), Address::from_hex("343c43a37d37dff08ae8c4a11544c718abb4fcf8").unwrap());
+    assert_eq!(Address::create(&sender, 2), Address::from_hex("f778b86fa74e846c4f0a1fbd1335fe81c00a0c91").unwrap());
+    assert_eq!(Address::create(&sender, 3), Address::from_hex("fffd933a0bc612844eaf0c6fe3e5b8e9b6c1d19c").unwrap());
+}
+
+#[test]
+fn test_create2_address() {
+    // the examples of EIP-1014
+    let address = Address::create2(&Address::default(), &Word::ZERO, &[0x00]);
+    assert_eq!(address, Address::from_hex("4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38").unwrap());
+    let sender = Address::from_hex("deadbeef00000000000000000000000000000000").unwrap();
+    let address = Address::create2(&sender, &Word::ZERO, &[0x00]);
+    assert_eq!(address, Address::from_hex("b928f69bb1d91cd65274e3c79d8986362984fda3").unwrap());
+    let sender = Address::from_hex("00000000000000000000000000000000deadbeef").unwrap();
+    let salt = Word::from_hex("00000000000000000000000000000000000000000000000000000000cafebabe").unwrap();
+    let address = Address::create2(&sender, &salt, &[0xde, 0xad, 0xbe, 0xef]);
+    assert_eq!(address, Address::from_hex("60f3f640a8508fc6a86d45df051962668e1e8ac7").unwrap());
+}
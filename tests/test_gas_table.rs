@@ -0,0 +1,50 @@
+use nrs_evm::core::{GasTable, Memory, DEFAULT_GAS_TABLE};
+
+#[test]
+fn test_gas_table_from_json() {
+    let gas_table: GasTable = serde_json::from_str(r#"
+    {
+        "static_costs":{"SLOAD":5000, "LOG1":1000},
+        "memory_quadratic_divisor":1024,
+        "log_byte_cost":1,
+        "sstore_set_cost":10000,
+        "sstore_clears_refund":0
+    }
+    "#).unwrap();
+    assert_eq!(gas_table.static_costs.get("SLOAD"), Some(&5000));
+    assert_eq!(gas_table.static_costs.get("LOG1"), Some(&1000));
+    assert_eq!(gas_table.memory_quadratic_divisor, 1024);
+    assert_eq!(gas_table.log_byte_cost, 1);
+    assert_eq!(gas_table.sstore_set_cost, 10000);
+    assert_eq!(gas_table.sstore_clears_refund, Some(0));
+    // the missing fields are the built-in values.
+    assert_eq!(gas_table.memory_word_cost, 3);
+    assert_eq!(gas_table.sha3_word_cost, 6);
+
+    let gas_table: GasTable = serde_json::from_str("{}").unwrap();
+    assert_eq!(gas_table, DEFAULT_GAS_TABLE);
+}
+
+#[test]
+fn test_gas_table_unknown_opcode() {
+    let result = serde_json::from_str::<GasTable>(r#"{"static_costs":{"SLOAD":5000, "SLOWLOAD":1}}"#);
+    assert!(result.is_err());
+    let result = serde_json::from_str::<GasTable>(r#"{"static_costs":{"INVALID":1}}"#);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn test_memory_cost() {
+    let mut memory = Memory::new();
+    memory.allocate(2080).unwrap();
+    assert_eq!(DEFAULT_GAS_TABLE.memory_cost(2080), memory.gas_cost(&DEFAULT_GAS_TABLE));
+    let gas_table = GasTable {
+        memory_word_cost: 1,
+        memory_quadratic_divisor: 0,
+        .. GasTable::default()
+    };
+    // 65 words without the quadratic cost.
+    assert_eq!(gas_table.memory_cost(2080), 65);
+    assert_eq!(memory.gas_cost(&gas_table), 65);
+    assert_eq!(memory.expansion_cost(2080 + 32, &gas_table), 1);
+}
@@ -1,48 +1,48 @@
-use nrs_evm::core::{U256, Memory, OffsetWrite, Word};
+use nrs_evm::core::{U256, Memory, OffsetWrite, Word, DEFAULT_GAS_TABLE};
 
 #[test]
 fn test_memory_write_offset() {
     let mut memory = Memory::new();
     assert_eq!(memory.len(), 0);
-    assert_eq!(memory.gas_cost(), 0);
+    assert_eq!(memory.gas_cost(&DEFAULT_GAS_TABLE), 0);
 
     let bytes: &[u8] = &[1; 32];
     memory.write(0_u64, bytes).unwrap();
     assert_eq!(memory.len(), 32);
-    assert_eq!(memory.gas_cost(), 3);
+    assert_eq!(memory.gas_cost(&DEFAULT_GAS_TABLE), 3);
 
     memory.write(1024_u64, bytes).unwrap();
     assert_eq!(memory.len(), 1056);
     assert_eq!(&memory.as_ref()[32..36], &[0_u8, 0, 0, 0]);
     assert_eq!(&memory.as_ref()[1022..1026], &[0_u8, 0, 1, 1]);
-    assert_eq!(memory.gas_cost(), 101);
+    assert_eq!(memory.gas_cost(&DEFAULT_GAS_TABLE), 101);
 
     let _res = memory.write(28_u64, &[10_u8, 11, 12, 13]).unwrap();
     assert_eq!(memory.len(), 1056);
     assert_eq!(&memory.as_ref()[22..32], &[1_u8, 1, 1, 1, 1, 1, 10, 11, 12, 13]);
-    assert_eq!(memory.gas_cost(), 101);
+    assert_eq!(memory.gas_cost(&DEFAULT_GAS_TABLE), 101);
 }
 
 #[test]
 fn test_memory_allocate() {
     let mut memory = Memory::new();
     assert_eq!(memory.len(), 0);
-    assert_eq!(memory.gas_cost(), 0);
+    assert_eq!(memory.gas_cost(&DEFAULT_GAS_TABLE), 0);
 
     memory.allocate(32).unwrap();
     assert_eq!(memory.len(), 32);
-    assert_eq!(memory.gas_cost(), 3);
+    assert_eq!(memory.gas_cost(&DEFAULT_GAS_TABLE), 3);
 
     memory.allocate(2080).unwrap();
     assert_eq!(memory.len(), 2080);
-    assert_eq!(memory.gas_cost(), 203);
+    assert_eq!(memory.gas_cost(&DEFAULT_GAS_TABLE), 203);
 }
 
 #[test]
 fn test_memory_read() {
     let mut memory = Memory::new();
     assert_eq!(memory.len(), 0);
-    assert_eq!(memory.gas_cost(), 0);
+    assert_eq!(memory.gas_cost(&DEFAULT_GAS_TABLE), 0);
 
     memory.allocate(2080).unwrap();
     let word = memory.read(0_u64).unwrap();
@@ -79,7 +79,7 @@ fn test_memory_read() {
 fn test_memory_read_multi_bytes() {
     let mut memory = Memory::new();
     assert_eq!(memory.len(), 0);
-    assert_eq!(memory.gas_cost(), 0);
+    assert_eq!(memory.gas_cost(&DEFAULT_GAS_TABLE), 0);
 
     memory.allocate(1030).unwrap();
     let data = memory.read_multi_bytes(0_u64, 1).unwrap();
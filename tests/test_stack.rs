@@ -0,0 +1,53 @@
+use nrs_evm::core::{Stack, StackError, U256, Word, STACK_LIMIT};
+
+#[test]
+fn test_stack_push_pop() {
+    let mut stack = Stack::new();
+    assert!(stack.is_empty());
+    assert_eq!(stack.pop(), Err(StackError::Underflow));
+
+    stack.push(Word::from(U256::from(1))).unwrap();
+    stack.push(Word::from(U256::from(2))).unwrap();
+    assert_eq!(stack.len(), 2);
+    assert_eq!(U256::from(stack.pop().unwrap()), U256::from(2));
+    assert_eq!(U256::from(stack.pop().unwrap()), U256::from(1));
+    assert_eq!(stack.pop(), Err(StackError::Underflow));
+}
+
+#[test]
+fn test_stack_limit() {
+    let mut stack = Stack::new();
+    for i in 0..STACK_LIMIT {
+        stack.push(Word::from(U256::from(i))).unwrap();
+    }
+    assert_eq!(stack.push(Word::ZERO), Err(StackError::Overflow));
+    assert_eq!(stack.dup(1), Err(StackError::Overflow));
+    assert_eq!(stack.len(), STACK_LIMIT);
+}
+
+#[test]
+fn test_stack_peek() {
+    let mut stack = Stack::new();
+    stack.push(Word::from(U256::from(1))).unwrap();
+    stack.push(Word::from(U256::from(2))).unwrap();
+    assert_eq!(U256::from(stack.peek(0).unwrap()), U256::from(2));
+    assert_eq!(U256::from(stack.peek(1).unwrap()), U256::from(1));
+    assert_eq!(stack.peek(2), Err(StackError::Underflow));
+}
+
+#[test]
+fn test_stack_dup_swap() {
+    let mut stack = Stack::new();
+    stack.push(Word::from(U256::from(1))).unwrap();
+    stack.push(Word::from(U256::from(2))).unwrap();
+    stack.push(Word::from(U256::from(3))).unwrap();
+
+    stack.dup(3).unwrap();
+    assert_eq!(U256::from(stack.peek(0).unwrap()), U256::from(1));
+    assert_eq!(stack.dup(5), Err(StackError::Underflow));
+
+    stack.swap(2).unwrap();
+    assert_eq!(U256::from(stack.peek(0).unwrap()), U256::from(2));
+    assert_eq!(U256::from(stack.peek(2).unwrap()), U256::from(1));
+    assert_eq!(stack.swap(4), Err(StackError::Underflow));
+}
@@ -0,0 +1,156 @@
+use nrs_evm::{execute_with_tracer, interpret, Context, ContextState};
+use nrs_evm::core::{Account, Address, CallKind, CallMessage, Hardfork, OnMemoryWorldState, Stack, TransactionContext, U256, Word};
+use nrs_evm::hex_util::FromHex;
+use nrs_evm::host::Host;
+use nrs_evm::instruction::{OpcodeFn, JUMP_TABLE};
+use nrs_evm::tracer::{JsonTracer, Tracer};
+
+#[derive(Default)]
+struct RecordTracer {
+    started: bool,
+    steps: Vec<usize>,
+    ended: usize,
+    stack_sizes: Vec<usize>,
+    memory_writes: Vec<(u64, Vec<u8>)>,
+    halted: Option<bool>,
+}
+
+impl Tracer for RecordTracer {
+    fn start(&mut self, _ctx: &Context) {
+        self.started = true;
+    }
+
+    fn step_start(&mut self, ctx: &Context, _op: &dyn OpcodeFn) {
+        self.steps.push(ctx.pc());
+    }
+
+    fn step_end(&mut self, _ctx: &Context) {
+        self.ended += 1;
+    }
+
+    fn stack_changed(&mut self, stack: &Stack) {
+        self.stack_sizes.push(stack.len());
+    }
+
+    fn memory_changed(&mut self, offset: u64, data: &[u8]) {
+        self.memory_writes.push((offset, data.to_vec()));
+    }
+
+    fn halt(&mut self, ctx: &Context) {
+        self.halted = Some(ctx.state() == &ContextState::Success);
+    }
+}
+
+#[test]
+fn test_tracer_hooks() {
+    let mut tracer = RecordTracer::default();
+    execute_with_tracer(vec![
+        0x60, 0xab,
+        0x60, 0x01,
+        0x53,
+        0x5b,
+        0x60, 0x01], 100000, &mut tracer);
+    assert!(tracer.started);
+    assert_eq!(tracer.steps, vec![0, 2, 4, 5, 6]);
+    assert_eq!(tracer.ended, 5);
+    // JUMPDEST does not touch the stack.
+    assert_eq!(tracer.stack_sizes, vec![1, 2, 0, 1]);
+    assert_eq!(tracer.memory_writes, vec![(1, vec![0xab])]);
+    assert_eq!(tracer.halted, Some(true));
+}
+
+#[test]
+fn test_tracer_exceptional_halt() {
+    let mut tracer = RecordTracer::default();
+    execute_with_tracer(vec![
+        0x60, 0x01,
+        0x01], 100000, &mut tracer);
+    assert_eq!(tracer.steps, vec![0, 2]);
+    assert_eq!(tracer.stack_sizes, vec![1]);
+    assert_eq!(tracer.halted, Some(false));
+}
+
+#[test]
+fn test_tracer_call() {
+    let address_a = Address::from_hex("00000000000000000000000000000000000000aa").unwrap();
+    let address_b = Address::from_hex("00000000000000000000000000000000000000bb").unwrap();
+    let mut world_state = OnMemoryWorldState::default();
+    // CALL(0xffff, 0xbb, 0, 0, 0, 0, 0)
+    world_state.insert(address_a.clone(), Account {
+        address: address_a.clone(),
+        code: vec![
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00,
+            0x60, 0x00, 0x60, 0xbb, 0x61, 0xff, 0xff, 0xf1],
+        .. Account::default()
+    });
+    world_state.insert(address_b.clone(), Account {
+        address: address_b,
+        code: vec![0x60, 0x01],
+        .. Account::default()
+    });
+    let message = CallMessage {
+        kind: CallKind::Call,
+        depth: 0,
+        gas: 100000,
+        destination: address_a.clone(),
+        code_address: address_a,
+        sender: Address::default(),
+        input_data: &[],
+        value: U256::zero(),
+        create2_salt: Word::ZERO,
+        is_static: false,
+    };
+    let mut tracer = RecordTracer::default();
+    let mut host = Host::new(world_state, TransactionContext::default());
+    let result = host.call_with_tracer(&message, &mut tracer);
+    assert_eq!(result.state, ContextState::Success);
+    // the step of the sub-call is traced after CALL.
+    assert_eq!(tracer.steps, vec![0, 2, 4, 6, 8, 10, 12, 15, 0]);
+    assert_eq!(tracer.halted, Some(true));
+}
+
+#[test]
+fn test_json_tracer() {
+    let mut tracer = JsonTracer::new(Vec::new());
+    execute_with_tracer(vec![
+        0x60, 0x80,
+        0x60, 0x00,
+        0x52,
+        0x60, 0x20,
+        0x60, 0x00,
+        0xf3], 100000, &mut tracer);
+    let output = String::from_utf8(tracer.into_inner()).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines, vec![
+        r#"{"pc":0,"op":96,"gas":"0x186a0","gasCost":"0x3","memSize":0,"stack":[],"depth":1,"refund":0,"opName":"PUSH1"}"#,
+        r#"{"pc":2,"op":96,"gas":"0x1869d","gasCost":"0x3","memSize":0,"stack":["0x80"],"depth":1,"refund":0,"opName":"PUSH1"}"#,
+        r#"{"pc":4,"op":82,"gas":"0x1869a","gasCost":"0x6","memSize":0,"stack":["0x80","0x0"],"depth":1,"refund":0,"opName":"MSTORE"}"#,
+        r#"{"pc":5,"op":96,"gas":"0x18694","gasCost":"0x3","memSize":32,"stack":[],"depth":1,"refund":0,"opName":"PUSH1"}"#,
+        r#"{"pc":7,"op":96,"gas":"0x18691","gasCost":"0x3","memSize":32,"stack":["0x20"],"depth":1,"refund":0,"opName":"PUSH1"}"#,
+        r#"{"pc":9,"op":243,"gas":"0x1868e","gasCost":"0x0","memSize":32,"stack":["0x20","0x0"],"depth":1,"refund":0,"opName":"RETURN"}"#,
+        r#"{"output":"0000000000000000000000000000000000000000000000000000000000000080","gasUsed":"0x12","pass":true}"#,
+    ]);
+}
+
+#[test]
+fn test_json_tracer_error() {
+    let mut tracer = JsonTracer::new(Vec::new());
+    execute_with_tracer(vec![0x01], 100, &mut tracer);
+    let output = String::from_utf8(tracer.into_inner()).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines[1], r#"{"output":"","gasUsed":"0x64","pass":false,"error":"StackUnderflow"}"#);
+}
+
+#[test]
+fn test_json_tracer_cold_access() {
+    // SLOAD(0) twice. the first one includes the cold access cost.
+    let mut tracer = JsonTracer::new(Vec::new());
+    let mut host = Host::new(OnMemoryWorldState::default(), TransactionContext::default());
+    let ctx = Context::new(vec![0x60, 0x00, 0x54, 0x60, 0x00, 0x54], 100000).with_hardfork(Hardfork::Berlin);
+    interpret(ctx, &JUMP_TABLE, &mut host, &mut tracer);
+    let output = String::from_utf8(tracer.into_inner()).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert!(lines[1].contains(r#""gasCost":"0x834""#));
+    assert!(lines[3].contains(r#""gasCost":"0x64""#));
+    assert!(lines[4].contains(r#""gasUsed":"0x89e""#));
+}
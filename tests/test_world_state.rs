@@ -1,5 +1,7 @@
-use nrs_evm::core::{Account, Address, OnMemoryWorldState, StorageStatus, Word, WorldStateInterface};
+use nrs_evm::core::{AccessStatus, Account, Address, EMPTY_CODE_HASH, Log, OnMemoryWorldState, StorageStatus, U256, Word, WorldStateInterface};
 use nrs_evm::hex_util::FromHex;
+use hash_db::Hasher;
+use keccak_hasher::KeccakHasher;
 
 #[test]
 fn test_set_storage() {
@@ -11,13 +13,50 @@ fn test_set_storage() {
     let key = Word::from_hex("0000000000000000000000000000000000000000000000000000000000000000").unwrap();
     let value = Word::from_hex("0000000000000000000000000000000000000000000000000000000000000001").unwrap();
     assert_eq!(wstate.set_storage(&address, &key, value), StorageStatus::StorageAdded);
+    wstate.finalize_transaction();
     assert_eq!(wstate.set_storage(&address, &key, value), StorageStatus::StorageUnchanged);
+    wstate.finalize_transaction();
 
     let value2 = Word::from_hex("0000000000000000000000000000000000000000000000000000000000000002").unwrap();
     assert_eq!(wstate.set_storage(&address, &key, value2), StorageStatus::StorageModified);
+    wstate.finalize_transaction();
     assert_eq!(wstate.set_storage(&address, &key, Word::ZERO.clone()), StorageStatus::StorageDeleted);
+    wstate.finalize_transaction();
     assert_eq!(wstate.set_storage(&address, &key, value), StorageStatus::StorageAdded);
 }
+
+#[test]
+fn test_set_storage_dirty() {
+    let mut wstate = OnMemoryWorldState::default();
+    let address = Address::from_hex("dd198a31e1dc7419aa5958097bffd6bdd1626ff1").unwrap();
+    wstate.insert(address.clone(), Account::default());
+
+    let key = Word::from_hex("0000000000000000000000000000000000000000000000000000000000000000").unwrap();
+    let value1 = Word::from_hex("0000000000000000000000000000000000000000000000000000000000000001").unwrap();
+    let value2 = Word::from_hex("0000000000000000000000000000000000000000000000000000000000000002").unwrap();
+    let value3 = Word::from_hex("0000000000000000000000000000000000000000000000000000000000000003").unwrap();
+
+    // the original value is 0
+    assert_eq!(wstate.set_storage(&address, &key, value1), StorageStatus::StorageAdded);
+    assert_eq!(wstate.set_storage(&address, &key, value2), StorageStatus::StorageModifiedAgain);
+    assert_eq!(wstate.set_storage(&address, &key, Word::ZERO), StorageStatus::StorageAddedDeleted);
+    assert_eq!(wstate.get_original_storage(&address, &key), Word::ZERO);
+    wstate.finalize_transaction();
+
+    // the original value is 1
+    assert_eq!(wstate.set_storage(&address, &key, value1), StorageStatus::StorageAdded);
+    wstate.finalize_transaction();
+    assert_eq!(wstate.get_original_storage(&address, &key), value1);
+    assert_eq!(wstate.set_storage(&address, &key, Word::ZERO), StorageStatus::StorageDeleted);
+    assert_eq!(wstate.set_storage(&address, &key, value1), StorageStatus::StorageDeletedRestored);
+    assert_eq!(wstate.set_storage(&address, &key, value2), StorageStatus::StorageModified);
+    assert_eq!(wstate.set_storage(&address, &key, value3), StorageStatus::StorageModifiedAgain);
+    assert_eq!(wstate.set_storage(&address, &key, value1), StorageStatus::StorageModifiedRestored);
+    assert_eq!(wstate.set_storage(&address, &key, Word::ZERO), StorageStatus::StorageDeleted);
+    assert_eq!(wstate.set_storage(&address, &key, value3), StorageStatus::StorageDeletedAdded);
+    assert_eq!(wstate.set_storage(&address, &key, Word::ZERO), StorageStatus::StorageModifiedDeleted);
+    assert_eq!(wstate.get_original_storage(&address, &key), value1);
+}
 #[test]
 fn test_get_storage() {
     let mut wstate = OnMemoryWorldState::default();
@@ -38,3 +77,221 @@ fn test_get_storage() {
     assert_eq!(wstate.get_storage(&address, &key), value);
     assert_eq!(wstate.get_storage(&address2, &key), value2);
 }
+
+#[test]
+fn test_storage_of_missing_account() {
+    let mut wstate = OnMemoryWorldState::default();
+    let address = Address::from_hex("dd198a31e1dc7419aa5958097bffd6bdd1626ff1").unwrap();
+    let value = Word::from(U256::from(1));
+    assert_eq!(wstate.get_storage(&address, &Word::ZERO), Word::ZERO);
+    assert_eq!(wstate.set_storage(&address, &Word::ZERO, value), StorageStatus::StorageAdded);
+    assert!(wstate.account_exists(&address));
+    assert_eq!(wstate.get_storage(&address, &Word::ZERO), value);
+}
+
+#[test]
+fn test_copy_code() {
+    let mut wstate = OnMemoryWorldState::default();
+    let account = Account {
+        code: vec![0x60, 0x01, 0x60, 0x02, 0x01],
+        .. Account::default()
+    };
+    let address = Address::from_hex("dd198a31e1dc7419aa5958097bffd6bdd1626ff1").unwrap();
+    let address2 = Address::from_hex("afc01a31e1dc7419aa5958097bffd6bdd1626ff1").unwrap();
+    wstate.insert(address.clone(), account);
+
+    assert_eq!(wstate.get_code_size(&address), 5);
+    assert_eq!(wstate.get_code_size(&address2), 0);
+
+    let mut buf = [0; 3];
+    assert_eq!(wstate.copy_code(&address, &mut buf), 3);
+    assert_eq!(buf, [0x60, 0x01, 0x60]);
+    assert_eq!(wstate.copy_code(&address2, &mut buf), 0);
+
+    assert_eq!(wstate.get_code_hash(&address2), Word::ZERO);
+}
+
+#[test]
+fn test_nonce_and_code() {
+    let mut wstate = OnMemoryWorldState::default();
+    let address = Address::from_hex("dd198a31e1dc7419aa5958097bffd6bdd1626ff1").unwrap();
+    assert_eq!(wstate.get_nonce(&address), 0);
+    assert!(!wstate.account_exists(&address));
+
+    wstate.increment_nonce(&address);
+    assert!(wstate.account_exists(&address));
+    assert_eq!(wstate.get_nonce(&address), 1);
+
+    wstate.set_code(&address, vec![0x60, 0x01]);
+    assert_eq!(wstate.get_code_size(&address), 2);
+    assert_eq!(wstate.get_nonce(&address), 1);
+}
+
+#[test]
+fn test_selfdestruct() {
+    let mut wstate = OnMemoryWorldState::default();
+    let address = Address::from_hex("dd198a31e1dc7419aa5958097bffd6bdd1626ff1").unwrap();
+    let beneficiary = Address::from_hex("afc01a31e1dc7419aa5958097bffd6bdd1626ff1").unwrap();
+    wstate.insert(address.clone(), Account {
+        balance: U256::from(10),
+        .. Account::default()
+    });
+
+    assert!(wstate.selfdestruct(&address, &beneficiary));
+    assert!(!wstate.selfdestruct(&address, &beneficiary));
+    assert_eq!(wstate.get_balance(&address), U256::zero());
+    assert_eq!(wstate.get_balance(&beneficiary), U256::from(10));
+    assert!(wstate.account_exists(&address));
+
+    wstate.finalize_transaction();
+    assert!(!wstate.account_exists(&address));
+    assert!(wstate.account_exists(&beneficiary));
+
+    wstate.create_account(&address);
+    assert!(wstate.is_created(&address));
+    // the nonce of the new contract is set by the caller (EIP-161).
+    assert_eq!(wstate.get_nonce(&address), 0);
+    wstate.finalize_transaction();
+    assert!(!wstate.is_created(&address));
+}
+
+#[test]
+fn test_code_hash() {
+    let mut wstate = OnMemoryWorldState::default();
+    let address = Address::from_hex("dd198a31e1dc7419aa5958097bffd6bdd1626ff1").unwrap();
+    wstate.insert(address.clone(), Account::default());
+    assert_eq!(wstate.get_code_hash(&address), EMPTY_CODE_HASH);
+    assert_eq!(EMPTY_CODE_HASH, Word::from(&KeccakHasher::hash(&[])));
+
+    let hash = Word::from(&KeccakHasher::hash(&[0x60, 0x01]));
+    wstate.set_code(&address, vec![0x60, 0x01]);
+    assert_eq!(wstate.get_code_hash(&address), hash);
+    assert_eq!(wstate.get_code_hash(&address), hash);
+
+    wstate.set_code(&address, vec![]);
+    assert_eq!(wstate.get_code_hash(&address), EMPTY_CODE_HASH);
+
+    // the hash is restored with the code.
+    let checkpoint = wstate.checkpoint();
+    wstate.set_code(&address, vec![0x60, 0x01]);
+    wstate.revert_to(checkpoint);
+    assert_eq!(wstate.get_code_hash(&address), EMPTY_CODE_HASH);
+
+    // the hash is computed for the world state loaded from JSON.
+    let wstate: OnMemoryWorldState = serde_json::from_str(r#"
+    {
+        "dd198a31e1dc7419aa5958097bffd6bdd1626ff1":{
+            "address":"dd198a31e1dc7419aa5958097bffd6bdd1626ff1",
+            "balance":"0",
+            "nonce":0,
+            "code":[96, 1],
+            "storage":{}
+        }
+    }
+    "#).unwrap();
+    assert_eq!(wstate.get_code_hash(&address), hash);
+
+    // the world state can be shared between threads.
+    fn assert_sync<T: Sync>(_: &T) {}
+    assert_sync(&wstate);
+}
+
+#[test]
+fn test_revert_to_checkpoint() {
+    let mut wstate = OnMemoryWorldState::default();
+    let address = Address::from_hex("dd198a31e1dc7419aa5958097bffd6bdd1626ff1").unwrap();
+    let address2 = Address::from_hex("afc01a31e1dc7419aa5958097bffd6bdd1626ff1").unwrap();
+    wstate.insert(address.clone(), Account {
+        balance: U256::from(10),
+        .. Account::default()
+    });
+    let key = Word::from_hex("0000000000000000000000000000000000000000000000000000000000000000").unwrap();
+    let value = Word::from_hex("0000000000000000000000000000000000000000000000000000000000000001").unwrap();
+
+    let checkpoint = wstate.checkpoint();
+    wstate.set_storage(&address, &key, value);
+    wstate.increment_nonce(&address);
+    wstate.set_code(&address, vec![0x60, 0x01]);
+    wstate.emit_log(Log::default());
+
+    let nested = wstate.checkpoint();
+    assert!(wstate.transfer(&address, &address2, U256::from(3)));
+    wstate.create_account(&address2);
+    assert!(wstate.selfdestruct(&address, &address2));
+    assert_eq!(wstate.get_balance(&address2), U256::from(10));
+
+    wstate.revert_to(nested);
+    assert!(!wstate.account_exists(&address2));
+    assert!(!wstate.is_created(&address2));
+    assert_eq!(wstate.get_balance(&address), U256::from(10));
+    // the account is not registered to be deleted.
+    assert!(wstate.selfdestruct(&address, &address2));
+    assert_eq!(wstate.get_storage(&address, &key), value);
+    assert_eq!(wstate.logs().len(), 1);
+
+    wstate.revert_to(checkpoint);
+    assert_eq!(wstate.get_storage(&address, &key), Word::ZERO);
+    assert_eq!(wstate.get_nonce(&address), 0);
+    assert_eq!(wstate.get_code_size(&address), 0);
+    assert_eq!(wstate.get_code_hash(&address), EMPTY_CODE_HASH);
+    assert_eq!(wstate.get_balance(&address), U256::from(10));
+    assert!(wstate.logs().is_empty());
+
+    // the inserted account is reverted.
+    let checkpoint = wstate.checkpoint();
+    wstate.insert(address2.clone(), Account {
+        code: vec![0x60, 0x01],
+        .. Account::default()
+    });
+    wstate.revert_to(checkpoint);
+    assert!(!wstate.account_exists(&address2));
+
+    // the committed changes are not reverted.
+    let checkpoint = wstate.checkpoint();
+    wstate.set_storage(&address, &key, value);
+    wstate.commit();
+    wstate.revert_to(checkpoint);
+    assert_eq!(wstate.get_storage(&address, &key), value);
+}
+
+#[test]
+fn test_take_logs() {
+    let mut wstate = OnMemoryWorldState::default();
+    let address = Address::from_hex("dd198a31e1dc7419aa5958097bffd6bdd1626ff1").unwrap();
+    let log = Log { address: address.clone(), .. Log::default() };
+
+    let checkpoint = wstate.checkpoint();
+    wstate.emit_log(Log::default());
+    assert_eq!(wstate.take_logs(), vec![Log::default()]);
+    wstate.emit_log(log.clone());
+    let nested = wstate.checkpoint();
+    wstate.emit_log(Log::default());
+
+    // only the logs after the checkpoint are removed.
+    wstate.revert_to(nested);
+    assert_eq!(wstate.logs(), &[log][..]);
+    // the taken logs are not touched.
+    wstate.revert_to(checkpoint);
+    assert!(wstate.logs().is_empty());
+}
+
+#[test]
+fn test_access() {
+    let mut world_state = OnMemoryWorldState::default();
+    let address = Address::from_hex("00000000000000000000000000000000000000aa").unwrap();
+    let key = Word::from(U256::from(1));
+    assert_eq!(world_state.access_account(&address), AccessStatus::Cold);
+    assert_eq!(world_state.access_account(&address), AccessStatus::Warm);
+
+    let checkpoint = world_state.checkpoint();
+    assert_eq!(world_state.access_storage(&address, &key), AccessStatus::Cold);
+    assert_eq!(world_state.access_storage(&address, &key), AccessStatus::Warm);
+    // the access in the reverted call is cold again.
+    world_state.revert_to(checkpoint);
+    assert_eq!(world_state.access_storage(&address, &key), AccessStatus::Cold);
+    assert_eq!(world_state.access_account(&address), AccessStatus::Warm);
+
+    world_state.finalize_transaction();
+    assert_eq!(world_state.access_account(&address), AccessStatus::Cold);
+    assert_eq!(world_state.access_storage(&address, &key), AccessStatus::Cold);
+}